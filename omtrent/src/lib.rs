@@ -14,6 +14,10 @@ pub enum Precision {
     Minute,
 }
 
+/// A point in time, where the less significant parts may be left out as wildcards (e.g. `19**`).
+///
+/// Note that `==` is exact structural equality, so `1950 != 19**`, while [`Ord`] treats wildcards
+/// as matching anything, so `1950.cmp(19**)` is `Equal`. Use [`TimeStamp::matches`] for the wildcard-aware check.
 #[derive(Default, PartialOrd, Eq, Hash, PartialEq, Debug, Clone)]
 pub struct TimeStamp {
     millenium: u32,
@@ -69,6 +73,13 @@ impl Display for TimeStamp {
 }
 
 impl TimeStamp {
+    /// Whether the two timestamps could refer to the same time, with wildcards matching any value.
+    ///
+    /// Same as `self.cmp(other).is_eq()`, unlike `==` which requires the wildcards to be in the same places.
+    pub fn matches(&self, other: &TimeStamp) -> bool {
+        self.cmp(other).is_eq()
+    }
+
     fn display(&self) -> String {
         let era = if self.after_christ { "AD" } else { "BC" };
 
@@ -183,11 +194,6 @@ impl TimeStamp {
         }
     }
 
-    fn parse_two_digits(iter: &mut impl Iterator<Item = char>) -> Option<u32> {
-        let (d1, d2) = (iter.next()?, iter.next()?);
-        Some(d1.to_string().parse::<u32>().ok()? * 10 + d2.to_string().parse::<u32>().ok()?)
-    }
-
     pub fn serialize(&self) -> String {
         let mut s = String::new();
         if !self.after_christ {
@@ -234,75 +240,338 @@ impl TimeStamp {
         s
     }
 
+    fn from_ymd(year: u32, month: u32, day: u32) -> Self {
+        Self {
+            millenium: year / 1000,
+            century: Some(year / 100 % 10),
+            decade: Some(year / 10 % 10),
+            year: Some(year % 10),
+            month: Some(month),
+            day: Some(day),
+            hour: None,
+            minute: None,
+            after_christ: true,
+        }
+    }
+
+    /// Parses ISO 8601 week dates (`2020-W11-7`, `2020-W11`) and ordinal dates (`2020-075`).
+    ///
+    /// `None` if the input isn't in either format.
+    fn from_iso_week_or_ordinal(s: &str) -> Option<Result<Self, ParseError>> {
+        let (year, rest) = s.strip_prefix('+').unwrap_or(s).split_once('-')?;
+        if year.len() != 4 || !year.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let year: i64 = year.parse().ok()?;
+
+        let ordinal = if let Some(week) = rest.strip_prefix('W') {
+            let (week, weekday) = week.split_once('-').unwrap_or((week, "1"));
+            if week.len() != 2 || weekday.len() != 1 {
+                return None;
+            }
+
+            let week: i64 = week.parse().ok()?;
+            let weekday: i64 = weekday.parse().ok()?;
+            let max_week = if iso_weeks_in_year(year) { 53 } else { 52 };
+            if !(1..=max_week).contains(&week) || !(1..=7).contains(&weekday) {
+                return None;
+            }
+
+            // January 4th is always in the first week.
+            (week - 1) * 7 + weekday + 4 - iso_weekday(year, 1, 4)
+        } else if rest.len() == 3 && rest.chars().all(|c| c.is_ascii_digit()) {
+            let ordinal: i64 = rest.parse().ok()?;
+            if !(1..=days_in_year(year)).contains(&ordinal) {
+                return None;
+            }
+            ordinal
+        } else {
+            return None;
+        };
+
+        let (year, ordinal) = if ordinal < 1 {
+            (year - 1, ordinal + days_in_year(year - 1))
+        } else if ordinal > days_in_year(year) {
+            (year + 1, ordinal - days_in_year(year))
+        } else {
+            (year, ordinal)
+        };
+
+        if !(1..=9999).contains(&year) {
+            return Some(Err(ParseError::YearOutOfRange { year }));
+        }
+
+        let mut day = ordinal;
+        let mut month = 1;
+        while day > days_in_month(year, month) {
+            day -= days_in_month(year, month);
+            month += 1;
+        }
+
+        Some(Ok(Self::from_ymd(year as u32, month as u32, day as u32)))
+    }
+
     pub fn from_string(s: String) -> Option<Self> {
+        Self::parse(&s).ok()
+    }
+
+    /// Like [`Self::from_string`] but tells what's wrong with the input when it can't be parsed.
+    pub fn parse(s: &str) -> Result<Self, ParseError> {
+        if let Some(selv) = Self::from_iso_week_or_ordinal(s) {
+            return selv;
+        }
+
         let mut selv = Self::default();
-        let mut s: Vec<char> = s.chars().collect();
-        let first = s.first()?;
-        if first != &'+' && first != &'-' {
-            s.insert(0, '+');
+        let mut chars = Chars::new(s);
+
+        match chars.peek() {
+            None => return Err(ParseError::Empty),
+            Some('-') => {
+                chars.next();
+                selv.after_christ = false;
+            }
+            Some('+') => {
+                chars.next();
+                selv.after_christ = true;
+            }
+            Some(_) => selv.after_christ = true,
         }
 
-        let mut iter = s.into_iter();
+        selv.millenium = chars.digit()?;
+        selv.century = chars.digit_or_wildcard()?;
+        selv.decade = chars.digit_or_wildcard()?;
+        selv.year = chars.digit_or_wildcard()?;
 
-        match iter.next()? {
-            '+' => selv.after_christ = true,
-            '-' => selv.after_christ = false,
-            _ => panic!(),
+        if !chars.separator(&['-', ' '])? {
+            return Ok(selv);
         }
 
-        selv.millenium = iter.next()?.to_string().parse().ok()?;
+        selv.month = Some(chars.two_digits()?);
 
-        selv.century = match iter.next()? {
-            '*' => None,
-            num => Some(num.to_string().parse().ok()?),
-        };
+        if !chars.separator(&['-', ' '])? {
+            return Ok(selv);
+        }
 
-        selv.decade = match iter.next()? {
-            '*' => None,
-            num => Some(num.to_string().parse().ok()?),
-        };
+        selv.day = Some(chars.two_digits()?);
 
-        selv.year = match iter.next()? {
-            '*' => None,
-            num => Some(num.to_string().parse().ok()?),
-        };
+        if !chars.separator(&['-', ' ', 'T'])? {
+            return Ok(selv);
+        }
 
-        match iter.next() {
-            Some('-') => {}
-            Some(' ') => {}
-            Some(_) => None?,
-            None => return Some(selv),
+        selv.hour = Some(chars.two_digits()?);
+
+        if !chars.separator(&[':'])? {
+            return Ok(selv);
+        }
+
+        selv.minute = Some(chars.two_digits()?);
+
+        if let Some((pos, ch)) = chars.next() {
+            return Err(ParseError::UnexpectedChar { pos, ch });
+        }
+
+        Ok(selv)
+    }
+}
+
+impl std::str::FromStr for TimeStamp {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Why a string couldn't be parsed into a [`TimeStamp`], positions are char indices into the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    /// The input ended in the middle of a part, e.g. a month with a single digit.
+    TooShort,
+    /// Expected a digit (or `*` for the wildcard parts of the year).
+    BadDigit {
+        pos: usize,
+        ch: char,
+    },
+    /// A separator or trailing character that doesn't belong there.
+    UnexpectedChar {
+        pos: usize,
+        ch: char,
+    },
+    /// A week or ordinal date that falls outside of the years 0001 to 9999.
+    YearOutOfRange {
+        year: i64,
+    },
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "timestamp is empty"),
+            Self::TooShort => write!(f, "timestamp ended unexpectedly"),
+            Self::BadDigit { pos, ch } => write!(f, "expected a digit at {pos}, found '{ch}'"),
+            Self::UnexpectedChar { pos, ch } => write!(f, "unexpected '{ch}' at {pos}"),
+            Self::YearOutOfRange { year } => write!(f, "year {year} is out of range"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Char iterator that keeps track of positions for the parse errors.
+struct Chars<'a> {
+    inner: std::iter::Peekable<std::iter::Enumerate<std::str::Chars<'a>>>,
+}
+
+impl<'a> Chars<'a> {
+    fn new(s: &'a str) -> Self {
+        Self {
+            inner: s.chars().enumerate().peekable(),
         }
+    }
 
-        selv.month = Some(Self::parse_two_digits(&mut iter)?);
+    fn peek(&mut self) -> Option<char> {
+        self.inner.peek().map(|(_, ch)| *ch)
+    }
+
+    fn next(&mut self) -> Option<(usize, char)> {
+        self.inner.next()
+    }
+
+    fn digit(&mut self) -> Result<u32, ParseError> {
+        let (pos, ch) = self.next().ok_or(ParseError::TooShort)?;
+        ch.to_digit(10).ok_or(ParseError::BadDigit { pos, ch })
+    }
 
-        match iter.next() {
-            Some('-') => {}
-            Some(' ') => {}
-            Some(_) => None?,
-            None => return Some(selv),
+    fn digit_or_wildcard(&mut self) -> Result<Option<u32>, ParseError> {
+        if self.peek() == Some('*') {
+            self.next();
+            return Ok(None);
         }
+        self.digit().map(Some)
+    }
 
-        selv.day = Some(Self::parse_two_digits(&mut iter)?);
+    fn two_digits(&mut self) -> Result<u32, ParseError> {
+        Ok(self.digit()? * 10 + self.digit()?)
+    }
 
-        match iter.next() {
-            Some('-') => {}
-            Some(' ') => {}
-            Some(_) => None?,
-            None => return Some(selv),
+    /// Consumes one of the allowed separators, `false` if the input has ended.
+    fn separator(&mut self, allowed: &[char]) -> Result<bool, ParseError> {
+        match self.next() {
+            None => Ok(false),
+            Some((_, ch)) if allowed.contains(&ch) => Ok(true),
+            Some((pos, ch)) => Err(ParseError::UnexpectedChar { pos, ch }),
         }
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_year(year: i64) -> i64 {
+    if is_leap_year(year) {
+        366
+    } else {
+        365
+    }
+}
 
-        selv.hour = Some(Self::parse_two_digits(&mut iter)?);
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Day of the week from 1 (monday) to 7 (sunday).
+fn iso_weekday(year: i64, month: i64, day: i64) -> i64 {
+    const OFFSETS: [i64; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    let year = if month < 3 { year - 1 } else { year };
+    let weekday = (year + year.div_euclid(4) - year.div_euclid(100)
+        + year.div_euclid(400)
+        + OFFSETS[month as usize - 1]
+        + day)
+        .rem_euclid(7);
+    if weekday == 0 {
+        7
+    } else {
+        weekday
+    }
+}
 
-        match iter.next() {
-            Some(':') => {}
-            Some(_) => None?,
-            None => return Some(selv),
+/// Whether the ISO week-numbering year has 53 weeks.
+fn iso_weeks_in_year(year: i64) -> bool {
+    let jan_first = iso_weekday(year, 1, 1);
+    jan_first == 4 || (jan_first == 3 && is_leap_year(year))
+}
+
+/// A period of time, `end` is `None` when it's still ongoing.
+#[derive(Eq, Hash, PartialEq, Debug, Clone)]
+pub struct TimeRange {
+    pub start: TimeStamp,
+    pub end: Option<TimeStamp>,
+}
+
+impl TimeRange {
+    pub fn new(start: TimeStamp, end: Option<TimeStamp>) -> Self {
+        Self { start, end }
+    }
+
+    pub fn contains(&self, time: &TimeStamp) -> bool {
+        if time.cmp(&self.start).is_lt() {
+            return false;
+        }
+
+        match &self.end {
+            Some(end) => time.cmp(end).is_le(),
+            None => true,
         }
+    }
+
+    pub fn overlaps(&self, other: &TimeRange) -> bool {
+        let starts_before_other_ends = match &other.end {
+            Some(end) => self.start.cmp(end).is_le(),
+            None => true,
+        };
+
+        let other_starts_before_end = match &self.end {
+            Some(end) => other.start.cmp(end).is_le(),
+            None => true,
+        };
+
+        starts_before_other_ends && other_starts_before_end
+    }
+}
+
+impl Ord for TimeRange {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.start
+            .cmp(&other.start)
+            .then_with(|| match (&self.end, &other.end) {
+                (Some(l), Some(r)) => l.cmp(r),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            })
+    }
+}
 
-        selv.minute = Some(Self::parse_two_digits(&mut iter)?);
+impl PartialOrd for TimeRange {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-        Some(selv)
+impl Display for TimeRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let start = self.start.display();
+        match &self.end {
+            Some(end) => write!(f, "{}–{}", start.trim(), end.display().trim()),
+            None => write!(f, "{}–", start.trim()),
+        }
     }
 }
 
@@ -332,4 +601,163 @@ mod tests {
         let bar = TimeStamp::from_string("-19**".to_string()).unwrap();
         assert!(foo.cmp(&bar).is_ge());
     }
+
+    #[test]
+    fn test_matches() {
+        let exact = TimeStamp::from_string("1950".to_string()).unwrap();
+        let wildcard = TimeStamp::from_string("19**".to_string()).unwrap();
+
+        assert!(exact.matches(&wildcard));
+        assert!(wildcard.matches(&exact));
+        assert_ne!(exact, wildcard);
+
+        let other = TimeStamp::from_string("1850".to_string()).unwrap();
+        assert!(!other.matches(&wildcard));
+        assert!(exact.matches(&exact.clone()));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(TimeStamp::parse(""), Err(ParseError::Empty));
+        assert_eq!(TimeStamp::parse("19"), Err(ParseError::TooShort));
+        assert_eq!(TimeStamp::parse("2020-0"), Err(ParseError::TooShort));
+        assert_eq!(
+            TimeStamp::parse("19x0"),
+            Err(ParseError::BadDigit { pos: 2, ch: 'x' })
+        );
+        assert_eq!(
+            TimeStamp::parse("-19a0"),
+            Err(ParseError::BadDigit { pos: 3, ch: 'a' })
+        );
+        assert_eq!(
+            TimeStamp::parse("2020/03"),
+            Err(ParseError::UnexpectedChar { pos: 4, ch: '/' })
+        );
+        assert_eq!(
+            TimeStamp::parse("2020-03-15 12x30"),
+            Err(ParseError::UnexpectedChar { pos: 13, ch: 'x' })
+        );
+        assert_eq!(
+            TimeStamp::parse("2020-03-15 12:30xyz"),
+            Err(ParseError::UnexpectedChar { pos: 16, ch: 'x' })
+        );
+    }
+
+    #[test]
+    fn test_from_str() {
+        let parsed: TimeStamp = "19**".parse().unwrap();
+        assert_eq!(Some(parsed), TimeStamp::from_string("19**".to_string()));
+        assert!("nope".parse::<TimeStamp>().is_err());
+    }
+
+    #[test]
+    fn test_calendar_dates() {
+        let date = ts("2020-03-15");
+        assert_eq!(date, TimeStamp::from_ymd(2020, 3, 15));
+        assert_eq!(date.to_string(), "15 mar 2020 ");
+
+        let month = ts("2020-03");
+        assert_eq!(month.month, Some(3));
+        assert_eq!(month.day, None);
+        assert_eq!(month.to_string(), "mar 2020 ");
+
+        let year = ts("2020");
+        assert_eq!(year.year, Some(0));
+        assert_eq!(year.decade, Some(2));
+        assert_eq!(year.month, None);
+
+        let wildcard = ts("20**");
+        assert_eq!(wildcard.century, Some(0));
+        assert_eq!(wildcard.decade, None);
+        assert_eq!(wildcard.year, None);
+
+        let time = ts("2020-03-15T14:30");
+        assert_eq!(time.hour, Some(14));
+        assert_eq!(time.minute, Some(30));
+    }
+
+    #[test]
+    fn test_week_dates() {
+        assert_eq!(ts("2020-W11-7"), ts("2020-03-15"));
+        assert_eq!(ts("2020-W11"), ts("2020-03-09"));
+        assert_eq!(ts("2020-W01-1"), ts("2019-12-30"));
+        assert_eq!(ts("2020-W53-5"), ts("2021-01-01"));
+        assert!(TimeStamp::from_string("2021-W53".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_ordinal_dates() {
+        assert_eq!(ts("2020-075"), ts("2020-03-15"));
+        assert_eq!(ts("2019-365"), ts("2019-12-31"));
+        assert!(TimeStamp::from_string("2019-366".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_week_and_ordinal_year_range() {
+        assert_eq!(ts("0001-W01-1"), ts("0001-01-01"));
+        assert_eq!(ts("0001-001"), ts("0001-01-01"));
+
+        for s in ["0000-W01", "0000-W52-7", "0000-001", "0000-366"] {
+            assert!(
+                matches!(TimeStamp::parse(s), Err(ParseError::YearOutOfRange { .. })),
+                "{s}"
+            );
+        }
+
+        assert_eq!(
+            TimeStamp::parse("9999-W52-7"),
+            Err(ParseError::YearOutOfRange { year: 10000 })
+        );
+    }
+
+    fn ts(s: &str) -> TimeStamp {
+        TimeStamp::from_string(s.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_range_display() {
+        let range = TimeRange::new(ts("1939"), Some(ts("1945")));
+        assert_eq!(range.to_string(), "1939–1945");
+
+        let range = TimeRange::new(ts("1991"), None);
+        assert_eq!(range.to_string(), "1991–");
+    }
+
+    #[test]
+    fn test_range_contains() {
+        let range = TimeRange::new(ts("1939"), Some(ts("1945")));
+        assert!(range.contains(&ts("1942")));
+        assert!(range.contains(&ts("1945")));
+        assert!(!range.contains(&ts("1946")));
+        assert!(!range.contains(&ts("1938")));
+
+        let open = TimeRange::new(ts("1991"), None);
+        assert!(open.contains(&ts("2020")));
+        assert!(!open.contains(&ts("1990")));
+    }
+
+    #[test]
+    fn test_range_overlaps() {
+        let ww2 = TimeRange::new(ts("1939"), Some(ts("1945")));
+        let cold_war = TimeRange::new(ts("1947"), Some(ts("1991")));
+        let korea = TimeRange::new(ts("1950"), Some(ts("1953")));
+        let open = TimeRange::new(ts("1944"), None);
+
+        assert!(!ww2.overlaps(&cold_war));
+        assert!(cold_war.overlaps(&korea));
+        assert!(korea.overlaps(&cold_war));
+        assert!(open.overlaps(&ww2));
+        assert!(open.overlaps(&korea));
+        assert!(!TimeRange::new(ts("1946"), None).overlaps(&ww2));
+    }
+
+    #[test]
+    fn test_range_ord() {
+        let closed = TimeRange::new(ts("1939"), Some(ts("1945")));
+        let open = TimeRange::new(ts("1939"), None);
+        let later = TimeRange::new(ts("1940"), Some(ts("1941")));
+
+        assert!(closed < open);
+        assert!(open < later);
+    }
 }
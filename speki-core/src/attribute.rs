@@ -1,4 +1,4 @@
-use std::{pin::Pin, time::Duration};
+use std::{collections::HashSet, pin::Pin, time::Duration};
 
 use serde::{Deserialize, Serialize};
 use speki_dto::{Item, ModifiedSource};
@@ -17,6 +17,8 @@ pub struct Attribute {
     // the answer to the attribute should be part of this
     // for example, if the attribute is 'where was {} born?' the type should be of concept place
     pub back_type: Option<CardId>,
+    /// The answer is a list of cards rather than a single one, e.g. 'who are the children of {}?'
+    pub list_valued: bool,
     pub card_provider: CardProvider,
     pub last_modified: Duration,
     pub source: ModifiedSource,
@@ -45,6 +47,7 @@ impl Attribute {
             id: dto.id,
             class: dto.class,
             back_type: dto.back_type,
+            list_valued: dto.list_valued,
             last_modified: dto.last_modified,
             source: dto.source,
             card_provider: provider,
@@ -57,6 +60,7 @@ impl Attribute {
             id: self.id,
             class: self.class,
             back_type: self.back_type,
+            list_valued: self.list_valued,
             last_modified: self.last_modified,
             deleted: false,
             source: self.source,
@@ -80,6 +84,39 @@ impl Attribute {
         attrs
     }
 
+    /// Loads the attributes of a class along with those inherited from its ancestor classes.
+    ///
+    /// An attribute shadows any attribute of an ancestor class with the same pattern,
+    /// so only the one from the most derived class is kept.
+    pub async fn load_inherited(app: &App, class: CardId) -> eyre::Result<Vec<Self>> {
+        let mut classes = vec![class];
+        if let Some(card) = app.card_provider.load(class).await {
+            classes.extend(card.load_ancestor_classes().await?);
+        }
+
+        let attrs: Vec<Attribute> = app
+            .provider
+            .attrs
+            .load_all()
+            .await
+            .into_values()
+            .map(|a| Self::from_dto(a, app.card_provider.clone()))
+            .collect();
+
+        let mut seen_patterns = HashSet::new();
+        let mut out = vec![];
+
+        for class in classes {
+            for attr in attrs.iter().filter(|attr| attr.class == class) {
+                if seen_patterns.insert(attr.pattern.clone()) {
+                    out.push(attr.clone());
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
     /*
 
     pub fn load_from_class(class: CardId, instance: CardId, app: &App) -> Vec<Self> {
@@ -110,6 +147,8 @@ pub struct AttributeDTO {
     pub class: CardId,
     pub back_type: Option<CardId>,
     #[serde(default)]
+    pub list_valued: bool,
+    #[serde(default)]
     pub last_modified: Duration,
     #[serde(default)]
     pub deleted: bool,
@@ -152,3 +191,62 @@ impl Item for AttributeDTO {
         self.deleted = true;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::{Attribute, AttributeDTO, AttributeId};
+    use crate::{card::CardId, testing::test_app, App};
+
+    async fn add_attribute(app: &App, class: CardId, pattern: &str) -> AttributeId {
+        let attribute = AttributeDTO {
+            pattern: pattern.to_string(),
+            id: AttributeId::new_v4(),
+            class,
+            back_type: None,
+            list_valued: false,
+            last_modified: Default::default(),
+            deleted: false,
+            source: Default::default(),
+        };
+        let id = attribute.id;
+        app.provider.attrs.save_item(attribute).await;
+        id
+    }
+
+    #[test]
+    fn test_load_inherited() {
+        block_on(async {
+            let (app, _) = test_app();
+            let animal = app
+                .add_class("animal".to_string(), "".to_string(), None)
+                .await;
+            let dog = app
+                .add_class("dog".to_string(), "".to_string(), Some(animal))
+                .await;
+
+            let name = add_attribute(&app, animal, "name of {}?").await;
+            let animal_legs = add_attribute(&app, animal, "legs of {}?").await;
+            let dog_legs = add_attribute(&app, dog, "legs of {}?").await;
+            let breed = add_attribute(&app, dog, "breed of {}?").await;
+
+            let ids = |attrs: Vec<Attribute>| {
+                let mut ids: Vec<AttributeId> = attrs.iter().map(|attr| attr.id).collect();
+                ids.sort();
+                ids
+            };
+            let sorted = |mut ids: Vec<AttributeId>| {
+                ids.sort();
+                ids
+            };
+
+            // The dog's own pattern shadows the one it would inherit from animal.
+            let inherited = Attribute::load_inherited(&app, dog).await.unwrap();
+            assert_eq!(ids(inherited), sorted(vec![name, dog_legs, breed]));
+
+            let inherited = Attribute::load_inherited(&app, animal).await.unwrap();
+            assert_eq!(ids(inherited), sorted(vec![name, animal_legs]));
+        });
+    }
+}
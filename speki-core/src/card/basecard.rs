@@ -1,4 +1,4 @@
-use omtrent::TimeStamp;
+use omtrent::{TimeRange, TimeStamp};
 
 use super::*;
 use crate::{attribute::AttributeId, audio::AudioId, card_provider::CardProvider, App, Attribute};
@@ -7,7 +7,7 @@ pub type CardId = Uuid;
 
 /// Represents the card without userdata, the part that can be freely shared among different users.
 #[derive(Clone, Serialize, Deserialize, Debug)]
-#[serde(from = "RawCard", into = "RawCard")]
+#[serde(try_from = "Value", into = "RawCard")]
 pub struct BaseCard {
     pub id: CardId,
     pub ty: CardType,
@@ -17,6 +17,10 @@ pub struct BaseCard {
     pub source: ModifiedSource,
     pub front_audio: Option<AudioId>,
     pub back_audio: Option<AudioId>,
+    /// Unix time the card was created, not known for cards created before this was tracked.
+    pub created: Option<Duration>,
+    /// The card this one was created as the reverse of, see [`crate::App::create_reverse`].
+    pub reverse_of: Option<CardId>,
 }
 
 impl BaseCard {
@@ -37,6 +41,86 @@ impl BaseCard {
             source: Default::default(),
             front_audio: None,
             back_audio: None,
+            created: None,
+            reverse_of: None,
+        }
+    }
+
+    /// Warns about card sides that are longer than the limits, cards should be kept short and atomic.
+    pub async fn length_warnings(
+        &self,
+        provider: &CardProvider,
+        limits: LengthLimits,
+    ) -> Vec<CardWarning> {
+        let mut warnings = vec![];
+
+        let front = self.ty.display_front(provider).await;
+        warnings.extend(limits.check_front(&front));
+
+        if let Some(BackSide::Text(back)) = self.ty.backside() {
+            warnings.extend(limits.check_back(&back));
+        }
+
+        warnings
+    }
+}
+
+/// Max amount of characters on each side of a card before [`BaseCard::length_warnings`] complains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthLimits {
+    pub front: usize,
+    pub back: usize,
+}
+
+impl LengthLimits {
+    pub fn check_front(&self, front: &str) -> Option<CardWarning> {
+        let len = front.chars().count();
+        (len > self.front).then_some(CardWarning::FrontTooLong {
+            len,
+            max: self.front,
+        })
+    }
+
+    pub fn check_back(&self, back: &str) -> Option<CardWarning> {
+        let len = back.chars().count();
+        (len > self.back).then_some(CardWarning::BackTooLong {
+            len,
+            max: self.back,
+        })
+    }
+}
+
+impl Default for LengthLimits {
+    fn default() -> Self {
+        Self {
+            front: 150,
+            back: 300,
+        }
+    }
+}
+
+/// Problems with a card that shouldn't stop it from being saved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CardWarning {
+    FrontTooLong { len: usize, max: usize },
+    BackTooLong { len: usize, max: usize },
+}
+
+impl std::fmt::Display for CardWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CardWarning::FrontTooLong { len, max } => {
+                write!(
+                    f,
+                    "front is {len} characters long, consider keeping it below {max}"
+                )
+            }
+            CardWarning::BackTooLong { len, max } => {
+                write!(
+                    f,
+                    "back is {len} characters long, consider keeping it below {max}"
+                )
+            }
         }
     }
 }
@@ -52,13 +136,24 @@ impl From<RawCard> for BaseCard {
             source: raw.source,
             front_audio: raw.front_audio,
             back_audio: raw.back_audio,
+            created: raw.created,
+            reverse_of: raw.reverse_of,
         }
     }
 }
 
+impl TryFrom<Value> for BaseCard {
+    type Error = serde_json::Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        migrate(value).map(Self::from)
+    }
+}
+
 impl From<BaseCard> for RawCard {
     fn from(card: BaseCard) -> Self {
         RawCard {
+            version: RawCard::CURRENT_VERSION,
             id: card.id,
             data: from_any(card.ty),
             dependencies: card.dependencies,
@@ -68,6 +163,8 @@ impl From<BaseCard> for RawCard {
             tags: Default::default(),
             front_audio: card.front_audio,
             back_audio: card.back_audio,
+            created: card.created,
+            reverse_of: card.reverse_of,
         }
     }
 }
@@ -195,6 +292,60 @@ impl AttributeCard {
             .await
             .unwrap_or_else(|| "oops, instance is deleted".to_string())
     }
+
+    /// Ensures the attribute is declared on the class of the instance or one of its ancestor classes,
+    /// and that the answer fits the attribute.
+    ///
+    /// Catches attribute cards that went stale after their instance changed class.
+    /// List-valued attributes need a list of cards as the answer, other attributes can't have one.
+    /// If the attribute has a back type, every card in the answer must be of that class.
+    pub async fn validate(&self, provider: &CardProvider) -> eyre::Result<()> {
+        let Some(attr) = provider.provider.attrs.load_item(self.attribute).await else {
+            eyre::bail!("attribute {} not found", self.attribute);
+        };
+
+        let Some(instance) = provider.load(self.instance).await else {
+            eyre::bail!("instance {} not found", self.instance);
+        };
+
+        let classes = instance.load_ancestor_classes().await?;
+
+        if !classes.contains(&attr.class) {
+            eyre::bail!(
+                "attribute {} belongs to class {} which is not a class of instance {}",
+                self.attribute,
+                attr.class,
+                self.instance
+            );
+        }
+
+        let answers = match (&self.back, attr.list_valued) {
+            (BackSide::List(ids), true) => ids.clone(),
+            (_, true) => eyre::bail!("attribute {} takes a list of cards", self.attribute),
+            (BackSide::List(_), false) => {
+                eyre::bail!("attribute {} takes a single answer", self.attribute)
+            }
+            (BackSide::Card(id), false) => vec![*id],
+            (_, false) => vec![],
+        };
+
+        if let Some(back_type) = attr.back_type {
+            for id in answers {
+                let Some(answer) = provider.load(id).await else {
+                    eyre::bail!("answer {id} not found");
+                };
+
+                let mut classes = answer.load_ancestor_classes().await?;
+                classes.push(id);
+
+                if !classes.contains(&back_type) {
+                    eyre::bail!("answer {id} is not of class {back_type}");
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// A specific instance of a class
@@ -222,9 +373,14 @@ pub struct InstanceCard {
 /// 1. It represents a property of an instance or sub-class.
 /// 2. The set of the class it belongs to is large
 /// 3. The property in that set is rare, but not unique
+///
+/// A statement can also be reviewed as a true/false prompt by giving it a truth value,
+/// which also allows keeping false statements around as cards.
 #[derive(PartialEq, Debug, Clone)]
 pub struct StatementCard {
     pub front: String,
+    /// Whether the statement is true, `None` for plain statements that aren't asked about.
+    pub truth: Option<bool>,
 }
 
 #[async_trait::async_trait(?Send)]
@@ -243,6 +399,10 @@ pub struct EventCard {
 }
 
 impl EventCard {
+    pub fn time_range(&self) -> TimeRange {
+        TimeRange::new(self.start_time.clone(), self.end_time.clone())
+    }
+
     /// Returns `true` if the other event fits within self-event's timeline
     fn inner_valid_sub_event(&self, other: &Self) -> bool {
         if other.start_time < self.start_time {
@@ -293,6 +453,24 @@ impl CardTrait for EventCard {
     }
 }
 
+/// The way one card references another.
+#[derive(Serialize, Deserialize, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum CardRefType {
+    /// A dependency added manually to the card.
+    ExplicitDependency,
+    /// The class an instance belongs to.
+    ClassOfInstance,
+    /// The parent class of a class.
+    ParentClass,
+    /// The instance an attribute card asks about.
+    InstanceOfAttribute,
+    /// The parent event of an event.
+    ParentEvent,
+    /// A card referenced from the backside.
+    BackRef,
+}
+
 #[async_trait::async_trait(?Send)]
 pub trait CardTrait: Debug + Clone {
     async fn get_dependencies(&self) -> BTreeSet<CardId>;
@@ -337,6 +515,41 @@ impl CardType {
         }
     }
 
+    /// Same as [`Self::get_dependencies`] but tagged with how each card is referenced.
+    pub fn refs(&self) -> BTreeSet<(CardRefType, CardId)> {
+        let mut refs = BTreeSet::default();
+
+        if let Some(back) = self.backside() {
+            for id in back.dependencies() {
+                refs.insert((CardRefType::BackRef, id));
+            }
+        }
+
+        match self {
+            CardType::Instance(card) => {
+                refs.insert((CardRefType::ClassOfInstance, card.class));
+            }
+            CardType::Class(card) => {
+                if let Some(id) = card.parent_class {
+                    refs.insert((CardRefType::ParentClass, id));
+                }
+            }
+            CardType::Attribute(card) => {
+                refs.insert((CardRefType::InstanceOfAttribute, card.instance));
+            }
+            CardType::Event(card) => {
+                if let Some(id) = card.parent_event {
+                    refs.insert((CardRefType::ParentEvent, id));
+                }
+            }
+            CardType::Normal(_) => {}
+            CardType::Unfinished(_) => {}
+            CardType::Statement(_) => {}
+        }
+
+        refs
+    }
+
     pub async fn display_front(&self, provider: &CardProvider) -> String {
         match self {
             CardType::Instance(card) => card.name.clone(),
@@ -421,6 +634,37 @@ impl CardType {
         };
     }
 
+    /// Points every reference to `from` at `to` instead, used when merging two cards.
+    pub fn replace_ref(&mut self, from: CardId, to: CardId) {
+        if let Some(back) = self.mut_backside() {
+            back.replace_ref(from, to);
+        }
+
+        let swap = |id: &mut CardId| {
+            if *id == from {
+                *id = to;
+            }
+        };
+
+        match self {
+            CardType::Instance(InstanceCard { class, .. }) => swap(class),
+            CardType::Class(ClassCard { parent_class, .. }) => {
+                if let Some(parent) = parent_class {
+                    swap(parent);
+                }
+            }
+            CardType::Attribute(AttributeCard { instance, .. }) => swap(instance),
+            CardType::Event(EventCard { parent_event, .. }) => {
+                if let Some(parent) = parent_event {
+                    swap(parent);
+                }
+            }
+            CardType::Normal(_) => {}
+            CardType::Unfinished(_) => {}
+            CardType::Statement(_) => {}
+        }
+    }
+
     pub fn type_name(&self) -> &str {
         match self {
             CardType::Unfinished(_) => "unfinished",
@@ -502,6 +746,8 @@ struct RawType {
     start_time: Option<String>,
     end_time: Option<String>,
     parent_event: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    truth: Option<bool>,
 }
 
 impl RawType {
@@ -512,6 +758,8 @@ impl RawType {
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 struct RawCard {
+    #[serde(default)]
+    version: u32,
     id: Uuid,
     #[serde(flatten)]
     data: RawType,
@@ -529,6 +777,48 @@ struct RawCard {
     front_audio: Option<AudioId>,
     #[serde(default)]
     back_audio: Option<AudioId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reverse_of: Option<Uuid>,
+}
+
+impl RawCard {
+    const CURRENT_VERSION: u32 = 1;
+}
+
+/// Upgrades a serialized card from any previous version into the current [`RawCard`] shape.
+fn migrate(mut value: Value) -> Result<RawCard, serde_json::Error> {
+    let version = value.get("version").and_then(Value::as_u64).unwrap_or(0);
+
+    if version < 1 {
+        migrate_v0(&mut value);
+    }
+
+    serde_json::from_value(value)
+}
+
+/// Version 0 cards had no version field, and might be missing the card type or a backside.
+///
+/// A card without a type is a normal card if it has a backside, otherwise unfinished.
+/// A normal card without a backside is also considered unfinished.
+fn migrate_v0(value: &mut Value) {
+    let Some(obj) = value.as_object_mut() else {
+        return;
+    };
+
+    let has_back = obj.contains_key("back");
+
+    let ty = match obj.get("ty").and_then(Value::as_str) {
+        None if has_back => "normal",
+        None => "unfinished",
+        Some("normal") if !has_back => "unfinished",
+        Some(ty) => ty,
+    }
+    .to_string();
+
+    obj.insert("ty".to_string(), Value::String(ty));
+    obj.insert("version".to_string(), Value::from(1));
 }
 
 impl Item for BaseCard {
@@ -621,6 +911,29 @@ impl BackSide {
         }
     }
 
+    pub fn replace_ref(&mut self, from: CardId, to: CardId) {
+        match self {
+            BackSide::Card(card_id) => {
+                if *card_id == from {
+                    *card_id = to;
+                }
+            }
+            BackSide::List(vec) => {
+                for card_id in vec.iter_mut() {
+                    if *card_id == from {
+                        *card_id = to;
+                    }
+                }
+                let mut seen = HashSet::new();
+                vec.retain(|id| seen.insert(*id));
+            }
+            BackSide::Text(_) => {}
+            BackSide::Time(_) => {}
+            BackSide::Trivial => {}
+            BackSide::Invalid => {}
+        }
+    }
+
     pub fn is_ref(&self) -> bool {
         matches!(self, Self::Card(_))
     }
@@ -762,6 +1075,7 @@ fn into_any(raw: RawType) -> CardType {
         .into(),
         CType::Statement => StatementCard {
             front: raw.front.unwrap(),
+            truth: raw.truth,
         }
         .into(),
         CType::Event => EventCard {
@@ -815,8 +1129,9 @@ fn from_any(ty: CardType) -> RawType {
             raw.back = Some(back);
             raw.class = parent_class;
         }
-        CardType::Statement(StatementCard { front }) => {
+        CardType::Statement(StatementCard { front, truth }) => {
             raw.front = Some(front);
+            raw.truth = truth;
         }
         CardType::Event(EventCard {
             front,
@@ -833,3 +1148,282 @@ fn from_any(ty: CardType) -> RawType {
 
     raw
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::executor::block_on;
+    use speki_dto::Item;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{attribute::AttributeDTO, testing::test_app};
+
+    const NORMAL_ID: &str = "6f1c0e4a-2b7d-4c1e-9a3f-1d2e3f4a5b6c";
+    const DEPENDENCY_ID: &str = "0b9d7a52-8c3e-4f21-b6a4-7e5d3c2b1a09";
+
+    fn parse(s: &str) -> BaseCard {
+        <BaseCard as Item>::deserialize(Uuid::nil(), s.to_string())
+    }
+
+    fn version(card: &BaseCard) -> i64 {
+        let value: toml::Value = toml::from_str(&Item::serialize(card)).unwrap();
+        value["version"].as_integer().unwrap()
+    }
+
+    #[test]
+    fn test_migrate_v0_normal() {
+        let card = parse(
+            r#"
+id = "6f1c0e4a-2b7d-4c1e-9a3f-1d2e3f4a5b6c"
+ty = "normal"
+front = "capital of france?"
+back = "paris"
+dependencies = ["0b9d7a52-8c3e-4f21-b6a4-7e5d3c2b1a09"]
+source = "Local"
+
+[last_modified]
+secs = 1700000000
+nanos = 0
+"#,
+        );
+
+        assert_eq!(card.id, Uuid::parse_str(NORMAL_ID).unwrap());
+        assert_eq!(
+            card.ty,
+            CardType::Normal(NormalCard {
+                front: "capital of france?".to_string(),
+                back: BackSide::Text("paris".to_string()),
+            })
+        );
+        assert_eq!(
+            card.dependencies,
+            BTreeSet::from([Uuid::parse_str(DEPENDENCY_ID).unwrap()])
+        );
+        assert_eq!(card.last_modified, Duration::from_secs(1700000000));
+        assert!(!card.deleted);
+        assert_eq!(card.created, None);
+        assert_eq!(version(&card), 1);
+    }
+
+    #[test]
+    fn test_migrate_v0_missing_type() {
+        let card = parse(
+            r#"
+id = "6f1c0e4a-2b7d-4c1e-9a3f-1d2e3f4a5b6c"
+front = "capital of france?"
+back = "paris"
+"#,
+        );
+        assert_eq!(
+            card.ty,
+            CardType::Normal(NormalCard {
+                front: "capital of france?".to_string(),
+                back: BackSide::Text("paris".to_string()),
+            })
+        );
+
+        let card = parse(
+            r#"
+id = "6f1c0e4a-2b7d-4c1e-9a3f-1d2e3f4a5b6c"
+front = "capital of france?"
+"#,
+        );
+        assert_eq!(
+            card.ty,
+            CardType::Unfinished(UnfinishedCard {
+                front: "capital of france?".to_string(),
+            })
+        );
+
+        let card = parse(
+            r#"
+id = "6f1c0e4a-2b7d-4c1e-9a3f-1d2e3f4a5b6c"
+ty = "normal"
+front = "capital of france?"
+deleted = true
+"#,
+        );
+        assert_eq!(
+            card.ty,
+            CardType::Unfinished(UnfinishedCard {
+                front: "capital of france?".to_string(),
+            })
+        );
+        assert!(card.deleted);
+    }
+
+    #[test]
+    fn test_migrate_v1() {
+        let card = parse(
+            r#"
+version = 1
+id = "6f1c0e4a-2b7d-4c1e-9a3f-1d2e3f4a5b6c"
+ty = "instance"
+front = "paris"
+class = "0b9d7a52-8c3e-4f21-b6a4-7e5d3c2b1a09"
+source = "Local"
+
+[last_modified]
+secs = 1700000000
+nanos = 0
+
+[created]
+secs = 1690000000
+nanos = 0
+"#,
+        );
+
+        let class = Uuid::parse_str(DEPENDENCY_ID).unwrap();
+        assert_eq!(
+            card.ty,
+            CardType::Instance(InstanceCard {
+                name: "paris".to_string(),
+                back: None,
+                class,
+            })
+        );
+        assert_eq!(card.created, Some(Duration::from_secs(1690000000)));
+        assert_eq!(card.last_modified, Duration::from_secs(1700000000));
+
+        // Saving and loading again doesn't change anything.
+        let reloaded = parse(&Item::serialize(&card));
+        assert_eq!(reloaded.ty, card.ty);
+        assert_eq!(reloaded.created, card.created);
+        assert_eq!(reloaded.last_modified, card.last_modified);
+        assert_eq!(version(&reloaded), 1);
+    }
+
+    #[test]
+    fn test_length_limits() {
+        let limits = LengthLimits { front: 5, back: 3 };
+
+        assert_eq!(limits.check_front("abcde"), None);
+        assert_eq!(
+            limits.check_front("abcdef"),
+            Some(CardWarning::FrontTooLong { len: 6, max: 5 })
+        );
+        // Counted in characters, not bytes.
+        assert_eq!(limits.check_front("æøåæø"), None);
+
+        assert_eq!(limits.check_back("abc"), None);
+        assert_eq!(
+            limits.check_back("abcd"),
+            Some(CardWarning::BackTooLong { len: 4, max: 3 })
+        );
+    }
+
+    #[test]
+    fn test_length_warnings() {
+        futures::executor::block_on(async {
+            let (app, _) = crate::testing::test_app();
+            let limits = LengthLimits { front: 5, back: 3 };
+
+            let card = BaseCard::new(NormalCard {
+                front: "abcdef".to_string(),
+                back: BackSide::Text("abcd".to_string()),
+            });
+            assert_eq!(
+                card.length_warnings(&app.card_provider, limits).await,
+                vec![
+                    CardWarning::FrontTooLong { len: 6, max: 5 },
+                    CardWarning::BackTooLong { len: 4, max: 3 },
+                ]
+            );
+
+            let card = BaseCard::new(NormalCard {
+                front: "abc".to_string(),
+                back: BackSide::Text("ab".to_string()),
+            });
+            assert!(card
+                .length_warnings(&app.card_provider, limits)
+                .await
+                .is_empty());
+        });
+    }
+
+    #[test]
+    fn test_list_valued_attribute() {
+        block_on(async {
+            let (app, _) = test_app();
+            let person = app
+                .add_class("person".to_string(), "".to_string(), None)
+                .await;
+            let city = app
+                .add_class("city".to_string(), "".to_string(), None)
+                .await;
+            let capital = app
+                .add_class("capital".to_string(), "".to_string(), Some(city))
+                .await;
+            let alice = app
+                .add_instance("alice".to_string(), None::<String>, person)
+                .await;
+            let paris = app
+                .add_instance("paris".to_string(), None::<String>, capital)
+                .await;
+            let lyon = app
+                .add_instance("lyon".to_string(), None::<String>, city)
+                .await;
+
+            let add_attribute = |list_valued: bool| {
+                let app = &app;
+                async move {
+                    let attribute = AttributeDTO {
+                        pattern: "cities of {}?".to_string(),
+                        id: AttributeId::new_v4(),
+                        class: person,
+                        back_type: Some(city),
+                        list_valued,
+                        last_modified: Default::default(),
+                        deleted: false,
+                        source: Default::default(),
+                    };
+                    let id = attribute.id;
+                    app.provider.attrs.save_item(attribute).await;
+                    id
+                }
+            };
+            let list = add_attribute(true).await;
+            let single = add_attribute(false).await;
+
+            let valid = |attribute: AttributeId, back: BackSide| {
+                let app = &app;
+                async move {
+                    let card = AttributeCard {
+                        attribute,
+                        back,
+                        instance: alice,
+                    };
+                    card.validate(&app.card_provider).await.is_ok()
+                }
+            };
+
+            // Answers of a subclass of the back type are fine too.
+            assert!(valid(list, BackSide::List(vec![paris, lyon])).await);
+            assert!(!valid(list, BackSide::List(vec![paris, alice])).await);
+            assert!(!valid(list, BackSide::Card(paris)).await);
+            assert!(!valid(list, BackSide::Text("paris".to_string())).await);
+
+            assert!(valid(single, BackSide::Card(paris)).await);
+            assert!(!valid(single, BackSide::Card(alice)).await);
+            assert!(!valid(single, BackSide::List(vec![paris])).await);
+            assert!(!valid(single, BackSide::Card(CardId::new_v4())).await);
+        });
+    }
+
+    #[test]
+    fn test_replace_ref_dedups_list() {
+        let keep = CardId::new_v4();
+        let other = CardId::new_v4();
+        let remove = CardId::new_v4();
+
+        let mut back = BackSide::List(vec![keep, other, remove]);
+        back.replace_ref(remove, keep);
+        assert_eq!(back, BackSide::List(vec![keep, other]));
+
+        let mut back = BackSide::List(vec![remove, other, keep]);
+        back.replace_ref(remove, keep);
+        assert_eq!(back, BackSide::List(vec![keep, other]));
+    }
+}
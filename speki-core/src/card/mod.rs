@@ -1,7 +1,7 @@
 use core::f32;
 use std::{
     cmp::{Ord, Ordering, PartialEq},
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, HashSet},
     fmt::Debug,
     sync::Arc,
     time::Duration,
@@ -17,13 +17,22 @@ use uuid::Uuid;
 use crate::{
     audio::Audio,
     card_provider::CardProvider,
-    metadata::{IsSuspended, Metadata},
-    recall_rate::{History, Recall, Review, SimpleRecall},
+    metadata::{Difficulty, IsSuspended, Metadata},
+    recall_rate::{Confidence, History, Recall, Review, SimpleRecall},
     RecallCalc, Recaller, TimeGetter,
 };
 
 pub type RecallRate = f32;
 
+/// What should be played when a side of a card is shown.
+///
+/// It's up to the frontend to decide how to synthesize the text.
+#[derive(Clone, Debug)]
+pub enum Speech {
+    Audio(Audio),
+    Tts(String),
+}
+
 mod basecard;
 
 pub use basecard::*;
@@ -87,22 +96,64 @@ impl Card {
 
     /// Loads all the ancestor ancestor classes
     /// for example, king, human male, human
-    pub async fn load_ancestor_classes(&self) -> Vec<CardId> {
+    ///
+    /// Errors if one of the classes in the chain doesn't exist.
+    pub async fn load_ancestor_classes(&self) -> eyre::Result<Vec<CardId>> {
         let mut classes = vec![];
         let mut parent_class = self.parent_class();
 
         while let Some(class) = parent_class {
             classes.push(class);
-            parent_class = self.card_provider.load(class).await.unwrap().parent_class();
+            let Some(card) = self.card_provider.load(class).await else {
+                eyre::bail!("class {class} not found");
+            };
+            parent_class = card.parent_class();
         }
 
-        classes
+        Ok(classes)
     }
 
     pub async fn dependents(&self) -> BTreeSet<Arc<Self>> {
         self.card_provider.dependents(self.id).await
     }
 
+    /// Amount of cards this card directly depends on, without loading them.
+    pub async fn dependency_count(&self) -> usize {
+        self.dependency_ids().await.len()
+    }
+
+    /// Amount of cards directly depending on this card, without loading them.
+    pub fn dependent_count(&self) -> usize {
+        self.card_provider.dependent_ids(self.id).len()
+    }
+
+    /// Amount of distinct cards this card depends on, directly or through other cards.
+    pub async fn recursive_dependency_count(&self) -> usize {
+        self.all_dependencies()
+            .await
+            .into_iter()
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Amount of distinct cards depending on this card, directly or through other cards.
+    pub async fn recursive_dependent_count(&self) -> usize {
+        self.all_dependents()
+            .await
+            .into_iter()
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Like [`Self::dependency_ids`] but loads the cards, skipping those that no longer exist.
+    pub async fn dependencies(&self) -> BTreeSet<Arc<Self>> {
+        self.card_provider
+            .load_many(self.dependency_ids().await)
+            .await
+            .into_iter()
+            .collect()
+    }
+
     pub fn meta(&self) -> Metadata {
         self.metadata.clone()
     }
@@ -117,6 +168,15 @@ impl Card {
         self.history.push(review);
         self.card_provider.save_reviews(self.history.clone()).await;
 
+        // A manual schedule is only for the next review.
+        if self.metadata.scheduled.take().is_some() {
+            self.card_provider
+                .provider
+                .metadata
+                .save_item(self.meta())
+                .await;
+        }
+
         self.history = self
             .card_provider
             .provider
@@ -132,6 +192,10 @@ impl Card {
         self.card_provider.time_provider()
     }
 
+    pub fn card_provider(&self) -> CardProvider {
+        self.card_provider.clone()
+    }
+
     pub fn lapses_last_month(&self) -> u32 {
         let current_time = self.time_provider().current_time();
         let day = Duration::from_secs(86400 * 30);
@@ -214,14 +278,14 @@ impl Card {
         self.base.ty.is_instance()
     }
 
-    pub async fn set_ref(mut self, reff: CardId) -> Card {
+    pub async fn set_ref(mut self, reff: CardId) -> eyre::Result<Card> {
         let backside = BackSide::Card(reff);
         self.base.ty = self.base.ty.set_backside(backside);
-        self.persist().await;
-        self
+        self.persist().await?;
+        Ok(self)
     }
 
-    pub async fn rm_dependency(&mut self, dependency: CardId) -> bool {
+    pub async fn rm_dependency(&mut self, dependency: CardId) -> eyre::Result<bool> {
         info!(
             "for removal, dependent: {}, -- dependency: {}",
             self.id(),
@@ -231,30 +295,36 @@ impl Card {
 
         if !res {
             info!("no dep to remove");
-            return false;
+            return Ok(false);
         }
 
         info!("dep was there: {res}");
         self.base.ty.remove_dep(dependency);
         self.card_provider.rm_dependent(dependency, self.id());
-        self.persist().await;
-        true
+        self.persist().await?;
+        Ok(true)
     }
 
-    pub async fn add_dependency(&mut self, dependency: CardId) {
+    pub async fn add_dependency(&mut self, dependency: CardId) -> eyre::Result<()> {
         info!("for card: {} inserting dependency: {}", self.id, dependency);
         if self.id() == dependency {
-            info!("not adding dep cause theyre the same lol");
-            return;
+            eyre::bail!("card {} can't depend on itself", self.id);
         }
 
         if self.all_dependents().await.contains(&dependency) {
-            tracing::warn!("failed to insert dependency due to cycle!");
-            return;
+            eyre::bail!(
+                "adding dependency {dependency} to {} would create a cycle",
+                self.id
+            );
         }
 
         self.base.dependencies.insert(dependency);
-        self.persist().await;
+        if let Err(e) = self.persist().await {
+            self.base.dependencies.remove(&dependency);
+            return Err(e);
+        }
+
+        Ok(())
     }
 
     pub fn back_side(&self) -> Option<&BackSide> {
@@ -269,31 +339,78 @@ impl Card {
         }
     }
 
+    /// Whether there's anything to reveal when reviewing, see [`Self::display_backside`].
+    pub fn has_back_side(&self) -> bool {
+        self.back_side().is_some() || self.truth().is_some()
+    }
+
+    /// The answer to a statement that's asked as a true/false prompt.
+    pub fn truth(&self) -> Option<bool> {
+        match self.card_type() {
+            CardType::Statement(card) => card.truth,
+            _ => None,
+        }
+    }
+
     pub async fn delete_card(self) {
         self.card_provider.remove_card(self.id).await;
     }
 
-    pub async fn into_type(mut self, data: impl Into<CardType>) -> Self {
+    pub async fn into_type(mut self, data: impl Into<CardType>) -> eyre::Result<Self> {
         self.base.ty = data.into();
-        self.persist().await;
-        self
+        self.persist().await?;
+        Ok(self)
+    }
+
+    /// Checks invariants that can't be expressed in the card type itself.
+    pub async fn validate(&self) -> eyre::Result<()> {
+        if self.dependency_ids().await.contains(&self.id) {
+            eyre::bail!("card depends on itself");
+        }
+
+        if let CardType::Attribute(card) = &self.base.ty {
+            card.validate(&self.card_provider).await?;
+        }
+
+        Ok(())
     }
 
     // Call this function every time card is mutated.
-    pub async fn persist(&mut self) {
+    //
+    // Invalid cards aren't written, see `Self::validate`.
+    pub async fn persist(&mut self) -> eyre::Result<()> {
         info!("persisting card: {}", self.id);
 
+        if let Err(e) = self.validate().await {
+            eyre::bail!("card {} is invalid: {e}", self.id);
+        }
+
+        self.save_unchecked().await;
+        Ok(())
+    }
+
+    /// Writes the card without validating it first.
+    ///
+    /// Only for when the caller has already made sure the card is valid, see [`Self::persist`].
+    pub(crate) async fn save_unchecked(&mut self) {
         let id = self.id;
+        // Invalidating drops the links of the old dependencies, so the current ones are set afterwards.
+        self.card_provider.invalidate_card_and_deps(self.id()).await;
         for dependency in self.dependency_ids().await {
             self.card_provider.set_dependent(dependency, id);
         }
 
-        self.card_provider.invalidate_card_and_deps(self.id()).await;
         self.card_provider.save_card(self.clone()).await;
         *self = Arc::unwrap_or_clone(self.card_provider.load(id).await.unwrap());
         info!("done persisting card: {}", self.id);
     }
 
+    /// Saves only the metadata, which doesn't depend on the card being valid.
+    async fn persist_metadata(&mut self) {
+        self.card_provider.save_metadata(self.meta()).await;
+        *self = Arc::unwrap_or_clone(self.card_provider.load(self.id).await.unwrap());
+    }
+
     pub async fn all_dependents(&self) -> Vec<CardId> {
         info!("getting dependents of: {}", self.id);
         let mut deps = vec![];
@@ -345,22 +462,25 @@ impl Card {
     }
 
     pub async fn display_backside(&self) -> Option<String> {
+        if let CardType::Event(event) = self.card_type() {
+            return Some(format!("🕒 {}", event.time_range()));
+        }
+
+        if let Some(truth) = self.truth() {
+            return Some(truth.to_string());
+        }
+
         Some(match self.back_side()? {
             BackSide::Trivial => format!("…"),
             BackSide::Invalid => "invalid: referenced a deleted card".to_string(),
             BackSide::Time(time) => format!("🕒 {}", time),
             BackSide::Text(s) => s.to_owned(),
-            BackSide::Card(id) => {
-                format!(
-                    "→ {}",
-                    self.card_provider.load(*id).await.unwrap().print().await
-                )
-            }
+            BackSide::Card(id) => format!("→ {}", self.card_provider.display_name(*id).await),
+            BackSide::List(list) if list.is_empty() => "<empty>".to_string(),
             BackSide::List(list) => format!("→ [{}]", {
                 let mut res = vec![];
                 for id in list {
-                    let s = self.card_provider.load(*id).await.unwrap().print().await;
-                    res.push(s);
+                    res.push(self.card_provider.display_name(*id).await);
                 }
 
                 res.join(", ")
@@ -368,10 +488,55 @@ impl Card {
         })
     }
 
+    /// The recorded front audio, falling back to text-to-speech of the front.
+    pub async fn front_speech(&self) -> Speech {
+        match &self.front_audio {
+            Some(audio) => Speech::Audio(audio.clone()),
+            None => Speech::Tts(self.print().await),
+        }
+    }
+
+    /// The recorded back audio, falling back to text-to-speech of the backside.
+    ///
+    /// Unlike [`Self::display_backside`] the text has no decorations, so references are just the names of the cards.
+    pub async fn back_speech(&self) -> Option<Speech> {
+        if let Some(audio) = &self.back_audio {
+            return Some(Speech::Audio(audio.clone()));
+        }
+
+        let text = match self.back_side()? {
+            BackSide::Trivial => return None,
+            BackSide::Invalid => return None,
+            BackSide::Time(time) => time.to_string(),
+            BackSide::Text(s) => s.to_owned(),
+            BackSide::Card(id) => self.card_provider.load(*id).await?.print().await,
+            BackSide::List(list) => {
+                let mut res = vec![];
+                for id in list {
+                    if let Some(card) = self.card_provider.load(*id).await {
+                        res.push(card.print().await);
+                    }
+                }
+                res.join(", ")
+            }
+        };
+
+        Some(Speech::Tts(text))
+    }
+
     pub fn history(&self) -> &History {
         &self.history
     }
 
+    pub fn review_count(&self) -> usize {
+        self.history.len()
+    }
+
+    /// How much to trust [`Self::recall_rate`], so estimates from few or inconsistent reviews can be shown as such.
+    pub fn recall_confidence(&self) -> Confidence {
+        self.history.confidence()
+    }
+
     fn current_time(&self) -> Duration {
         self.card_provider.time_provider().current_time()
     }
@@ -386,7 +551,21 @@ impl Card {
 
     pub fn recall_rate(&self) -> Option<RecallRate> {
         let now = self.current_time();
-        self.recaller.recall_rate(&self.history, now)
+        self.card_provider
+            .cached_recall_rate(self.id, &self.history, now, || {
+                self.recaller.recall_rate(&self.history, now)
+            })
+    }
+
+    /// The recall rate the card would have at unix time `at` if only its first `k` reviews had happened.
+    pub fn recall_rate_after(&self, k: usize, at: Duration) -> Option<RecallRate> {
+        self.recaller.recall_rate(&self.history.truncated(k), at)
+    }
+
+    /// The recall rate the card will have after `ahead` has passed.
+    pub fn recall_rate_ahead(&self, ahead: Duration) -> Option<RecallRate> {
+        let now = self.current_time();
+        self.recaller.recall_rate(&self.history, now + ahead)
     }
 
     pub fn maybeturity(&self) -> Option<f32> {
@@ -427,17 +606,116 @@ impl Card {
         self.base.ty.display_front(&self.card_provider).await
     }
 
+    /// The front as shown when reviewing, statements with a truth value are asked as a true/false prompt.
+    pub async fn display_review_front(&self) -> String {
+        match self.truth() {
+            Some(_) => format!("True or false? {}", self.print().await),
+            None => self.print().await,
+        }
+    }
+
     pub fn is_pending(&self) -> bool {
         self.history.is_empty()
     }
 
     pub fn is_suspended(&self) -> bool {
-        self.metadata.suspended.is_suspended()
+        self.metadata.suspended.is_suspended_at(self.current_time())
+    }
+
+    /// Hides the card from reviews for the given duration, after which it's reviewable again.
+    ///
+    /// This doesn't touch whether the card is suspended.
+    pub async fn snooze(&mut self, duration: Duration) {
+        self.metadata.snooze_until = Some(self.current_time() + duration);
+        self.persist_metadata().await;
+    }
+
+    /// Until when the card is snoozed, `None` if it isn't or the snooze has run out.
+    pub fn snoozed_until(&self) -> Option<Duration> {
+        self.metadata
+            .snooze_until
+            .filter(|until| *until > self.current_time())
+    }
+
+    /// Whether the card is still snoozed once `ahead` has passed.
+    pub fn is_snoozed(&self, ahead: Duration) -> bool {
+        self.metadata
+            .snooze_until
+            .is_some_and(|until| until > self.current_time() + ahead)
     }
 
     pub async fn set_suspend(&mut self, suspend: bool) {
         self.metadata.suspended = IsSuspended::from(suspend);
-        self.persist().await;
+        self.persist_metadata().await;
+    }
+
+    /// Whether the card is marked as trivial or has a trivial backside.
+    pub fn is_trivial(&self) -> bool {
+        self.metadata.trivial || matches!(self.back_side(), Some(BackSide::Trivial))
+    }
+
+    pub async fn set_trivial(&mut self, trivial: bool) {
+        self.metadata.trivial = trivial;
+        self.persist_metadata().await;
+    }
+
+    pub fn priority(&self) -> i32 {
+        self.metadata.priority
+    }
+
+    pub async fn set_priority(&mut self, priority: i32) {
+        self.metadata.priority = priority;
+        self.persist_metadata().await;
+    }
+
+    pub fn scheduled(&self) -> Option<Duration> {
+        self.metadata.scheduled
+    }
+
+    /// Forces the next review to happen at the given unix time, `None` reverts to the recall algorithm.
+    ///
+    /// The schedule is cleared once the card is reviewed.
+    pub async fn set_scheduled(&mut self, scheduled: Option<Duration>) {
+        self.metadata.scheduled = scheduled;
+        self.persist_metadata().await;
+    }
+
+    /// Whether a manually scheduled card is due after `ahead` has passed, `None` if it isn't manually scheduled.
+    pub fn scheduled_due(&self, ahead: Duration) -> Option<bool> {
+        let scheduled = self.metadata.scheduled?;
+        Some(self.current_time() + ahead >= scheduled)
+    }
+
+    /// The card this one is the reverse of, if it was created with [`crate::App::create_reverse`].
+    pub fn reverse_of(&self) -> Option<CardId> {
+        self.base.reverse_of
+    }
+
+    /// When the card was created, falling back to its first review for cards older than creation tracking.
+    pub fn created_at(&self) -> Option<Duration> {
+        self.base.created.or_else(|| self.first_reviewed_at())
+    }
+
+    pub fn first_reviewed_at(&self) -> Option<Duration> {
+        self.history.first().map(|review| review.timestamp)
+    }
+
+    pub fn needs_work(&self) -> bool {
+        self.metadata.needs_work
+    }
+
+    pub async fn set_needs_work(&mut self, needs_work: bool) {
+        self.metadata.needs_work = needs_work;
+        self.persist_metadata().await;
+    }
+
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        self.metadata.difficulty
+    }
+
+    pub async fn set_difficulty(&mut self, difficulty: Option<Difficulty>) {
+        self.metadata.difficulty = difficulty;
+        self.persist_metadata().await;
     }
 
     pub fn time_since_last_review(&self) -> Option<Duration> {
@@ -454,6 +732,15 @@ impl Card {
         deps
     }
 
+    /// All the cards this card depends on, along with how they're referenced.
+    pub fn dependency_refs(&self) -> BTreeSet<(CardRefType, CardId)> {
+        let mut refs = self.base.ty.refs();
+        for id in &self.base.dependencies {
+            refs.insert((CardRefType::ExplicitDependency, *id));
+        }
+        refs
+    }
+
     pub fn lapses(&self) -> u32 {
         self.history.lapses()
     }
@@ -796,3 +1083,472 @@ mod tests {
 }
 
 */
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::executor::block_on;
+    use speki_dto::TimeProvider;
+
+    use crate::{
+        audio::Audio,
+        card::{BackSide, BaseCard, Speech, StatementCard},
+        card_provider::CardProvider,
+        cardfilter::{CardFilter, MyNumOrd, NumOp},
+        recall_rate::{Confidence, Recall},
+        testing::test_app,
+        App, CardId,
+    };
+
+    fn due_filter() -> CardFilter {
+        CardFilter {
+            recall: Some(NumOp {
+                num: 0.8,
+                ord: MyNumOrd::Less,
+            }),
+            ..Default::default()
+        }
+    }
+
+    async fn is_due(app: &App, id: CardId) -> bool {
+        app.cards_filtered(due_filter())
+            .await
+            .iter()
+            .any(|card| card.id() == id)
+    }
+
+    #[test]
+    fn test_review_clears_schedule() {
+        block_on(async {
+            let (app, time) = test_app();
+            let id = app.add_card("front".to_string(), "back".to_string()).await;
+            app.submit_reviews(vec![(id, Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+            assert!(!is_due(&app, id).await);
+
+            let mut card = app.load_card(id).await.unwrap();
+            time.inc(Duration::from_secs(60));
+            card.set_scheduled(Some(time.current_time())).await;
+            assert!(is_due(&app, id).await);
+
+            card.add_review(Recall::Perfect).await;
+            assert_eq!(card.scheduled(), None);
+            assert!(!is_due(&app, id).await);
+            assert_eq!(app.load_card(id).await.unwrap().scheduled(), None);
+        });
+    }
+
+    #[test]
+    fn test_submitted_review_clears_schedule() {
+        block_on(async {
+            let (app, time) = test_app();
+            let id = app.add_card("front".to_string(), "back".to_string()).await;
+
+            let mut card = app.load_card(id).await.unwrap();
+            card.set_scheduled(Some(time.current_time())).await;
+            assert!(is_due(&app, id).await);
+
+            app.submit_reviews(vec![(id, Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+            assert!(!is_due(&app, id).await);
+        });
+    }
+
+    fn unsuspended_filter() -> CardFilter {
+        CardFilter {
+            suspended: Some(false),
+            ..Default::default()
+        }
+    }
+
+    async fn passes(app: &App, id: CardId, filter: CardFilter) -> bool {
+        app.cards_filtered(filter)
+            .await
+            .iter()
+            .any(|card| card.id() == id)
+    }
+
+    #[test]
+    fn test_snooze() {
+        block_on(async {
+            let (app, time) = test_app();
+            let id = app.add_card("front".to_string(), "back".to_string()).await;
+            let mut card = app.load_card(id).await.unwrap();
+
+            card.snooze(Duration::from_secs(3 * 86400)).await;
+            assert!(!card.is_suspended());
+            assert!(!passes(&app, id, unsuspended_filter()).await);
+            assert!(passes(&app, id, CardFilter::default()).await);
+
+            // Looking far enough ahead, the card is back.
+            let ahead = CardFilter {
+                ahead: Some(Duration::from_secs(4 * 86400)),
+                ..unsuspended_filter()
+            };
+            assert!(passes(&app, id, ahead).await);
+
+            time.inc(Duration::from_secs(3 * 86400 + 1));
+            let card = app.load_card(id).await.unwrap();
+            assert_eq!(card.snoozed_until(), None);
+            assert!(passes(&app, id, unsuspended_filter()).await);
+        });
+    }
+
+    #[test]
+    fn test_snoozing_keeps_suspension() {
+        block_on(async {
+            let (app, time) = test_app();
+            let id = app.add_card("front".to_string(), "back".to_string()).await;
+            let mut card = app.load_card(id).await.unwrap();
+
+            card.set_suspend(true).await;
+            card.snooze(Duration::from_secs(86400)).await;
+            assert!(card.is_suspended());
+            assert!(card.snoozed_until().is_some());
+
+            time.inc(Duration::from_secs(2 * 86400));
+            let card = app.load_card(id).await.unwrap();
+            assert!(card.is_suspended());
+            assert!(!passes(&app, id, unsuspended_filter()).await);
+        });
+    }
+
+    #[test]
+    fn test_invalid_card_is_not_written() {
+        block_on(async {
+            let (app, _) = test_app();
+            let id = app.add_card("front".to_string(), "back".to_string()).await;
+            let mut card = app.load_card(id).await.unwrap();
+
+            card.base.dependencies.insert(id);
+            assert!(card.persist().await.is_err());
+
+            let stored = app.load_card(id).await.unwrap();
+            assert!(!stored.dependency_ids().await.contains(&id));
+        });
+    }
+
+    #[test]
+    fn test_missing_class_is_an_error() {
+        block_on(async {
+            let (app, _) = test_app();
+            let class = app
+                .add_class("human".to_string(), "".to_string(), None)
+                .await;
+            let instance = app
+                .add_instance("socrates".to_string(), None::<String>, class)
+                .await;
+            let card = app.load_card(instance).await.unwrap();
+            assert_eq!(card.load_ancestor_classes().await.unwrap(), vec![class]);
+
+            let orphan = app
+                .add_instance("plato".to_string(), None::<String>, CardId::new_v4())
+                .await;
+            let card = app.load_card(orphan).await.unwrap();
+            assert!(card.load_ancestor_classes().await.is_err());
+        });
+    }
+
+    #[test]
+    fn test_statement_truth() {
+        block_on(async {
+            let (app, _) = test_app();
+            let provider = app.card_provider();
+            let asked = provider
+                .save_basecard(BaseCard::new(StatementCard {
+                    front: "the sun is a star".to_string(),
+                    truth: Some(true),
+                }))
+                .await
+                .id();
+            let plain = provider
+                .save_basecard(BaseCard::new(StatementCard {
+                    front: "the sun is a star".to_string(),
+                    truth: None,
+                }))
+                .await
+                .id();
+
+            let card = app.load_card(asked).await.unwrap();
+            assert!(card.has_back_side());
+            assert_eq!(card.truth(), Some(true));
+            assert_eq!(
+                card.display_review_front().await,
+                "True or false? the sun is a star"
+            );
+            assert_eq!(card.display_backside().await.as_deref(), Some("true"));
+            let explanation = app
+                .explain_reviewable(asked, &CardFilter::default())
+                .await
+                .unwrap();
+            assert!(explanation.has_back_side);
+
+            let card = app.load_card(plain).await.unwrap();
+            assert!(!card.has_back_side());
+            assert_eq!(card.display_review_front().await, "the sun is a star");
+            assert_eq!(card.display_backside().await, None);
+        });
+    }
+
+    fn tts(speech: Option<Speech>) -> Option<String> {
+        match speech? {
+            Speech::Tts(text) => Some(text),
+            Speech::Audio(_) => None,
+        }
+    }
+
+    #[test]
+    fn test_speech() {
+        block_on(async {
+            let (app, _) = test_app();
+            let paris = app.add_card("paris".to_string(), "".to_string()).await;
+            let rome = app.add_card("rome".to_string(), "".to_string()).await;
+            let text = app
+                .add_card("capital of france?".to_string(), "paris".to_string())
+                .await;
+            let reference = app
+                .add_card("capital of france?".to_string(), BackSide::Card(paris))
+                .await;
+            let list = app
+                .add_card("capitals?".to_string(), BackSide::List(vec![paris, rome]))
+                .await;
+            let trivial = app.add_card("trivial".to_string(), BackSide::Trivial).await;
+
+            let card = app.load_card(text).await.unwrap();
+            assert_eq!(
+                tts(Some(card.front_speech().await)).as_deref(),
+                Some("capital of france?")
+            );
+            assert_eq!(tts(card.back_speech().await).as_deref(), Some("paris"));
+
+            let card = app.load_card(reference).await.unwrap();
+            assert_eq!(tts(card.back_speech().await).as_deref(), Some("paris"));
+
+            let card = app.load_card(list).await.unwrap();
+            assert_eq!(
+                tts(card.back_speech().await).as_deref(),
+                Some("paris, rome")
+            );
+
+            let card = app.load_card(trivial).await.unwrap();
+            assert!(card.back_speech().await.is_none());
+
+            // Recorded audio is used instead of text-to-speech.
+            let audio = Audio::new(vec![1, 2, 3]);
+            app.provider.audios.save_item(audio.clone()).await;
+            let mut base = app.load_card(text).await.unwrap().base.clone();
+            base.back_audio = Some(audio.id);
+            app.card_provider.save_basecard(base).await;
+
+            let card = app.load_card(text).await.unwrap();
+            assert!(matches!(
+                card.back_speech().await,
+                Some(Speech::Audio(recorded)) if recorded.data == audio.data
+            ));
+            assert!(matches!(card.front_speech().await, Speech::Tts(_)));
+
+            // Same when all the cards are loaded at once.
+            app.card_provider.fill_cache().await;
+            let card = app.load_card(text).await.unwrap();
+            assert!(matches!(card.back_speech().await, Some(Speech::Audio(_))));
+            assert!(matches!(card.front_speech().await, Speech::Tts(_)));
+        });
+    }
+
+    #[test]
+    fn test_recall_rate_after() {
+        block_on(async {
+            let (app, time) = test_app();
+            let day = Duration::from_secs(86400);
+            let id = app.add_card("a".to_string(), "b".to_string()).await;
+            let first_only = app.add_card("c".to_string(), "d".to_string()).await;
+            app.submit_reviews(vec![
+                (id, Recall::Perfect, time.current_time()),
+                (first_only, Recall::Perfect, time.current_time()),
+            ])
+            .await
+            .unwrap();
+            time.inc(day);
+            app.submit_reviews(vec![(id, Recall::None, time.current_time())])
+                .await
+                .unwrap();
+            time.inc(day);
+
+            let card = app.load_card(id).await.unwrap();
+            let now = time.current_time();
+            assert_eq!(card.recall_rate_after(0, now), None);
+            assert_eq!(
+                card.recall_rate_after(1, now),
+                app.load_card(first_only).await.unwrap().recall_rate()
+            );
+            assert_eq!(card.recall_rate_after(2, now), card.recall_rate());
+            assert_eq!(card.recall_rate_after(10, now), card.recall_rate());
+            assert!(card.recall_rate_after(1, now) > card.recall_rate_after(2, now));
+
+            // Evaluating a prefix leaves the card's own history alone.
+            assert_eq!(card.history().inner().len(), 2);
+        });
+    }
+
+    #[test]
+    fn test_display_list_backside() {
+        block_on(async {
+            let (app, _) = test_app();
+            let paris = app.add_card("paris".to_string(), "".to_string()).await;
+            let empty = app
+                .add_card("capitals?".to_string(), BackSide::List(vec![]))
+                .await;
+            let dangling = app
+                .add_card(
+                    "capitals?".to_string(),
+                    BackSide::List(vec![paris, CardId::new_v4()]),
+                )
+                .await;
+
+            let card = app.load_card(empty).await.unwrap();
+            assert_eq!(card.display_backside().await.as_deref(), Some("<empty>"));
+
+            let card = app.load_card(dangling).await.unwrap();
+            assert_eq!(
+                card.display_backside().await,
+                Some(format!("→ [paris, {}]", CardProvider::DELETED_CARD_NAME))
+            );
+        });
+    }
+
+    #[test]
+    fn test_created_at() {
+        block_on(async {
+            let (app, time) = test_app();
+            let day = Duration::from_secs(86400);
+            let created = time.current_time();
+            let id = app.add_card("a".to_string(), "b".to_string()).await;
+
+            time.inc(day);
+            let mut base = app.load_card(id).await.unwrap().base.clone();
+            base.created = None;
+            app.card_provider.save_basecard(base).await;
+            let card = app.load_card(id).await.unwrap();
+            assert_eq!(card.created_at(), Some(created));
+            assert_eq!(card.first_reviewed_at(), None);
+
+            // Cards from before creation was tracked fall back to their first review.
+            let legacy = BaseCard::new(StatementCard {
+                front: "old".to_string(),
+                truth: None,
+            });
+            let legacy_id = legacy.id;
+            app.provider.cards.save_item(legacy).await;
+            assert_eq!(app.load_card(legacy_id).await.unwrap().created_at(), None);
+
+            let reviewed = time.current_time();
+            app.submit_reviews(vec![(legacy_id, Recall::Perfect, reviewed)])
+                .await
+                .unwrap();
+            time.inc(day);
+            app.submit_reviews(vec![(legacy_id, Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+            let card = app.load_card(legacy_id).await.unwrap();
+            assert_eq!(card.first_reviewed_at(), Some(reviewed));
+            assert_eq!(card.created_at(), Some(reviewed));
+        });
+    }
+
+    #[test]
+    fn test_reject_self_dependency() {
+        block_on(async {
+            let (app, _) = test_app();
+            let a = app.add_card("a".to_string(), "b".to_string()).await;
+            let b = app.add_card("c".to_string(), "d".to_string()).await;
+            let c = app.add_card("e".to_string(), "f".to_string()).await;
+
+            let mut card = app.load_card(a).await.unwrap();
+            assert!(card.add_dependency(a).await.is_err());
+            card.add_dependency(b).await.unwrap();
+            app.load_card(b)
+                .await
+                .unwrap()
+                .add_dependency(c)
+                .await
+                .unwrap();
+
+            // c -> a would close the loop a -> b -> c.
+            let mut card = app.load_card(c).await.unwrap();
+            assert!(card.add_dependency(a).await.is_err());
+            assert!(app
+                .load_card(c)
+                .await
+                .unwrap()
+                .dependency_ids()
+                .await
+                .is_empty());
+            assert!(!app.card_provider.set_dependent(a, a));
+
+            // Adding a second dependency keeps the first one linked.
+            let d = app.add_card("g".to_string(), "h".to_string()).await;
+            let mut card = app.load_card(a).await.unwrap();
+            card.add_dependency(d).await.unwrap();
+            assert_eq!(app.load_card(b).await.unwrap().dependent_count(), 1);
+            assert_eq!(app.load_card(d).await.unwrap().dependent_count(), 1);
+
+            // Cards that already depend on themselves don't pass validation.
+            let mut base = app.load_card(c).await.unwrap().base.clone();
+            base.dependencies.insert(c);
+            app.card_provider.save_basecard(base).await;
+            assert!(app.load_card(c).await.unwrap().validate().await.is_err());
+        });
+    }
+
+    #[test]
+    fn test_dependency_counts() {
+        block_on(async {
+            let (app, _) = test_app();
+            let base = app.add_card("base".to_string(), "".to_string()).await;
+            let left = app.add_card("left".to_string(), "".to_string()).await;
+            let right = app.add_card("right".to_string(), "".to_string()).await;
+            let top = app.add_card("top".to_string(), "".to_string()).await;
+            for (id, dep) in [(left, base), (right, base), (top, left), (top, right)] {
+                let mut card = app.load_card(id).await.unwrap();
+                card.add_dependency(dep).await.unwrap();
+            }
+
+            // The diamond reaches base twice but it's only counted once.
+            let card = app.load_card(top).await.unwrap();
+            assert_eq!(card.dependency_count().await, 2);
+            assert_eq!(card.recursive_dependency_count().await, 3);
+            assert_eq!(card.dependent_count(), 0);
+
+            let card = app.load_card(base).await.unwrap();
+            assert_eq!(card.dependency_count().await, 0);
+            assert_eq!(card.dependent_count(), 2);
+            assert_eq!(card.recursive_dependent_count().await, 3);
+        });
+    }
+
+    #[test]
+    fn test_review_count_and_confidence() {
+        block_on(async {
+            let (app, time) = test_app();
+            let id = app.add_card("a".to_string(), "b".to_string()).await;
+            let card = app.load_card(id).await.unwrap();
+            assert_eq!(card.review_count(), 0);
+            assert_eq!(card.recall_confidence(), Confidence::None);
+
+            let reviews = (0..8)
+                .map(|day| {
+                    let timestamp = time.current_time() + Duration::from_secs(day * 86400);
+                    (id, Recall::Perfect, timestamp)
+                })
+                .collect();
+            app.submit_reviews(reviews).await.unwrap();
+
+            let card = app.load_card(id).await.unwrap();
+            assert_eq!(card.review_count(), 8);
+            assert_eq!(card.recall_confidence(), Confidence::High);
+        });
+    }
+}
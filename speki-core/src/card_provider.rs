@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeSet, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fmt::Debug,
     future::Future,
     pin::Pin,
@@ -9,11 +9,12 @@ use std::{
 
 use dioxus_logger::tracing::{info, trace};
 use eyre::Result;
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
 use speki_dto::Item;
 
 use crate::{
     card::{BaseCard, CardId, RecallRate},
-    metadata::Metadata,
+    metadata::{Difficulty, Metadata},
     recall_rate::History,
     Card, Provider, Recaller, TimeGetter,
 };
@@ -29,6 +30,14 @@ pub struct CardProvider {
     time_provider: TimeGetter,
     recaller: Recaller,
     check_modified: bool,
+    subscribers: Arc<RwLock<Vec<UnboundedSender<CardChange>>>>,
+}
+
+/// A change to a card, sent to everyone who called [`CardProvider::subscribe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardChange {
+    Modified(CardId),
+    Deleted(CardId),
 }
 
 impl Debug for CardProvider {
@@ -52,12 +61,13 @@ impl CardProvider {
 
         self.invalidate_card(id).await;
 
+        // The dependencies of the other cards haven't changed, so they keep their place in the dependents index.
         for dep in card.all_dependents().await {
-            self.invalidate_card(dep).await;
+            self.uncache(dep);
         }
 
         for dep in card.all_dependencies().await {
-            self.invalidate_card(dep).await;
+            self.uncache(dep);
         }
 
         info!("done invalidating card with deps");
@@ -65,18 +75,11 @@ impl CardProvider {
 
     pub async fn invalidate_card(&self, id: CardId) {
         info!("invalidating card: {id}");
-        let mut guard = self.inner.write().unwrap();
-
-        let Some(card) = guard.cards.remove(&id) else {
+        let Some(card) = self.uncache(id) else {
             info!("oops no card");
             return;
         };
 
-        guard.metadata.remove(&id);
-        guard.reviews.remove(&id);
-
-        drop(guard);
-
         for dependency in card.card.dependency_ids().await {
             let dependent = card.card.id();
             self.rm_dependent(dependency, dependent);
@@ -84,6 +87,17 @@ impl CardProvider {
         info!("done invalidating");
     }
 
+    /// Drops the cached card without touching the dependents index.
+    fn uncache(&self, id: CardId) -> Option<CardCache> {
+        let mut guard = self.inner.write().unwrap();
+        let card = guard.cards.remove(&id)?;
+        guard.metadata.remove(&id);
+        guard.reviews.remove(&id);
+        guard.recall_rates.remove(&id);
+        guard.forget(id);
+        Some(card)
+    }
+
     pub fn rm_dependent(&self, dependency: CardId, dependent: CardId) -> bool {
         info!("rm dependent!!");
         let mut guard = self.inner.write().unwrap();
@@ -97,6 +111,11 @@ impl CardProvider {
     }
 
     pub fn set_dependent(&self, dependency: CardId, dependent: CardId) -> bool {
+        if dependency == dependent {
+            tracing::warn!("refusing to make {dependent} a dependent of itself");
+            return false;
+        }
+
         self.inner
             .write()
             .unwrap()
@@ -106,6 +125,23 @@ impl CardProvider {
             .insert(dependent)
     }
 
+    /// Receives a [`CardChange`] every time a card is saved or deleted.
+    pub fn subscribe(&self) -> UnboundedReceiver<CardChange> {
+        let (tx, rx) = unbounded();
+        self.subscribers.write().unwrap().push(tx);
+        rx
+    }
+
+    fn notify(&self, change: CardChange) {
+        let mut subscribers = self.subscribers.write().unwrap();
+        if subscribers.is_empty() {
+            return;
+        }
+
+        // Receivers that have been dropped are removed here.
+        subscribers.retain(|tx| tx.unbounded_send(change).is_ok());
+    }
+
     pub async fn remove_card(&self, card_id: CardId) {
         info!("cardprovider removing card: {card_id}");
         let _ = self.load(card_id).await; // ensure card is in cache first.
@@ -114,24 +150,74 @@ impl CardProvider {
         for card in self.load_all().await {
             info!("removing dependency for {}", card.id());
             let mut card = Arc::unwrap_or_clone(card);
-            card.rm_dependency(card_id).await;
+            if let Err(e) = card.rm_dependency(card_id).await {
+                tracing::error!("couldn't remove dependency on {card_id}: {e}");
+            }
         }
 
+        self.remove_card_from_all_collections(card_id).await;
+
         let (card, _revs, _deps) = self.remove_entry(card_id);
         let card = Arc::unwrap_or_clone(card.unwrap().card);
         self.provider.cards.delete_item(card.base).await;
+        self.notify(CardChange::Deleted(card_id));
         info!("done removing i guess");
     }
 
+    /// Removes every collection entry that is based on the given card.
+    pub async fn remove_card_from_all_collections(&self, card_id: CardId) {
+        for mut col in self.provider.collections.load_all().await.into_values() {
+            let before = col.dyncards.len();
+            col.dyncards
+                .retain(|dyncard| dyncard.card_id() != Some(card_id));
+
+            if col.dyncards.len() != before {
+                info!("removing {card_id} from collection {}", col.name);
+                self.provider.collections.save(col).await;
+            }
+        }
+    }
+
     fn remove_entry(&self, id: CardId) -> (Option<CardCache>, Option<RevCache>, Option<DepCache>) {
         info!("removing entry");
         let mut guard = self.inner.write().unwrap();
         let card = guard.cards.remove(&id);
         let rev = guard.reviews.remove(&id);
         let deps = guard.dependents.remove(&id);
+        guard.recall_rates.remove(&id);
+        guard.forget(id);
         (card, rev, deps)
     }
 
+    /// Reuses the recall rate of a card if it was computed recently from the same history, otherwise computes it with `calc`.
+    pub fn cached_recall_rate(
+        &self,
+        id: CardId,
+        history: &History,
+        now: Duration,
+        calc: impl FnOnce() -> Option<RecallRate>,
+    ) -> Option<RecallRate> {
+        let key = (history.len(), history.last().map(|review| review.timestamp));
+        let bucket = now.as_secs() / RECALL_CACHE_SECS;
+
+        if let Some(cached) = self.inner.read().unwrap().recall_rates.get(&id) {
+            if cached.history == key && cached.bucket == bucket {
+                return cached.recall_rate;
+            }
+        }
+
+        let recall_rate = calc();
+        self.inner.write().unwrap().recall_rates.insert(
+            id,
+            RecallCache {
+                history: key,
+                bucket,
+                recall_rate,
+            },
+        );
+        recall_rate
+    }
+
     pub fn min_rec_recall_rate(
         &self,
         id: CardId,
@@ -181,13 +267,20 @@ impl CardProvider {
     fn update_min_rec_recall(&self, id: CardId, cached_recall: RecallRate) {
         trace!("card: {id}: caching min rec recall: {cached_recall}");
         let mut guard = self.inner.write().unwrap();
-        guard.cards.get_mut(&id).unwrap().min_rec_recall = Some(cached_recall);
+        // The card might have been evicted while its dependencies were loaded.
+        if let Some(entry) = guard.cards.get_mut(&id) {
+            entry.min_rec_recall = Some(cached_recall);
+        }
     }
 
     pub async fn load_all_card_ids(&self) -> Vec<CardId> {
         self.provider.cards.load_ids().await
     }
 
+    pub async fn load_all_card_ids_sorted(&self) -> Vec<CardId> {
+        self.provider.cards.load_ids_sorted().await
+    }
+
     pub async fn fill_cache(&self) {
         info!("1");
         let mut cards: HashMap<CardId, CardCache> = Default::default();
@@ -206,7 +299,7 @@ impl CardProvider {
                 None => None,
             };
 
-            let back_audio = match card.front_audio {
+            let back_audio = match card.back_audio {
                 Some(id) => audios.get(&id).cloned(),
                 None => None,
             };
@@ -241,6 +334,11 @@ impl CardProvider {
         }
 
         let mut guard = self.inner.write().unwrap();
+        guard.last_used.clear();
+        guard.by_use.clear();
+        for id in cards.keys() {
+            guard.touch(*id);
+        }
         guard.cards = cards;
         guard.reviews = rev_caches;
     }
@@ -280,17 +378,158 @@ impl CardProvider {
         self.filtered_load(filter).await
     }
 
-    pub async fn dependents(&self, id: CardId) -> BTreeSet<Arc<Card>> {
-        trace!("dependents of: {}", id);
-        let mut out = BTreeSet::default();
-        let deps = self
-            .inner
+    /// The recursive dependencies of a card that can be reviewed, ordered so each card comes after its own dependencies.
+    pub async fn study_plan(&self, id: CardId) -> Vec<CardId> {
+        let mut order = vec![];
+        let mut visited = HashSet::new();
+        let mut stack = vec![(id, false)];
+
+        while let Some((id, expanded)) = stack.pop() {
+            if expanded {
+                order.push(id);
+                continue;
+            }
+
+            if !visited.insert(id) {
+                continue;
+            }
+
+            stack.push((id, true));
+
+            if let Some(card) = self.load(id).await {
+                for dep in card.dependency_ids().await {
+                    if !visited.contains(&dep) {
+                        stack.push((dep, false));
+                    }
+                }
+            }
+        }
+
+        let mut plan = vec![];
+        for dep in order {
+            if dep == id {
+                continue;
+            }
+
+            if let Some(card) = self.load(dep).await {
+                if card.is_finished() && !card.is_suspended() && !card.is_trivial() {
+                    plan.push(dep);
+                }
+            }
+        }
+
+        plan
+    }
+
+    pub async fn trivial_cards(&self) -> Vec<CardId> {
+        let filter = |card: Arc<Card>| async move { card.is_trivial() };
+        self.filtered_load(filter)
+            .await
+            .into_iter()
+            .map(|card| card.id())
+            .collect()
+    }
+
+    /// Cards that don't depend on any other card.
+    pub async fn roots(&self) -> Vec<CardId> {
+        self.graph_ends(true, false).await
+    }
+
+    /// Cards that no other card depends on.
+    pub async fn leaves(&self) -> Vec<CardId> {
+        self.graph_ends(false, true).await
+    }
+
+    /// Cards with neither dependencies nor dependents.
+    pub async fn isolated(&self) -> Vec<CardId> {
+        self.graph_ends(true, true).await
+    }
+
+    async fn graph_ends(&self, no_dependencies: bool, no_dependents: bool) -> Vec<CardId> {
+        // The dependents index is only complete once every card has been cached.
+        let cards = self.load_all().await;
+        let mut out = vec![];
+
+        for card in cards {
+            if no_dependencies && !card.dependency_ids().await.is_empty() {
+                continue;
+            }
+            if no_dependents && !self.dependent_ids(card.id()).is_empty() {
+                continue;
+            }
+            out.push(card.id());
+        }
+
+        out.sort();
+        out
+    }
+
+    /// Cards that have been flagged as needing work.
+    pub async fn needs_work_cards(&self) -> Vec<CardId> {
+        let filter = |card: Arc<Card>| async move { card.needs_work() };
+        self.filtered_load(filter)
+            .await
+            .into_iter()
+            .map(|card| card.id())
+            .collect()
+    }
+
+    /// Cards the user rated with the given difficulty.
+    pub async fn cards_with_difficulty(&self, difficulty: Difficulty) -> Vec<CardId> {
+        let filter = |card: Arc<Card>| async move { card.difficulty() == Some(difficulty) };
+        self.filtered_load(filter)
+            .await
+            .into_iter()
+            .map(|card| card.id())
+            .collect()
+    }
+
+    /// Unsuspended cards whose last review was longer ago than `older_than`.
+    pub async fn stale_cards(&self, older_than: Duration, include_unreviewed: bool) -> Vec<CardId> {
+        let filter = |card: Arc<Card>| async move {
+            if card.is_suspended() {
+                return false;
+            }
+
+            match card.time_since_last_review() {
+                Some(passed) => passed > older_than,
+                None => include_unreviewed,
+            }
+        };
+
+        self.filtered_load(filter)
+            .await
+            .into_iter()
+            .map(|card| card.id())
+            .collect()
+    }
+
+    /// Shown in place of the name of a card that doesn't exist anymore.
+    pub const DELETED_CARD_NAME: &'static str = "<deleted card>";
+
+    /// The front of the card as shown to the user, or [`Self::DELETED_CARD_NAME`] if it doesn't exist.
+    pub async fn display_name(&self, id: CardId) -> String {
+        match self.load(id).await {
+            Some(card) => card.print().await,
+            None => Self::DELETED_CARD_NAME.to_string(),
+        }
+    }
+
+    /// Ids of the cards directly depending on the given card, without loading them.
+    pub fn dependent_ids(&self, id: CardId) -> HashSet<CardId> {
+        self.inner
             .read()
             .unwrap()
             .dependents
             .get(&id)
             .cloned()
-            .unwrap_or_default();
+            .unwrap_or_default()
+    }
+
+    pub async fn dependents(&self, id: CardId) -> BTreeSet<Arc<Card>> {
+        trace!("dependents of: {}", id);
+        let mut out = BTreeSet::default();
+        let deps = self.dependent_ids(id);
 
         for dep in deps {
             if let Some(card) = self.load(dep).await {
@@ -301,6 +540,15 @@ impl CardProvider {
         out
     }
 
+    /// Loads all the given cards in one go, skipping those that don't exist.
+    pub async fn load_many(&self, ids: impl IntoIterator<Item = CardId>) -> Vec<Arc<Card>> {
+        futures::future::join_all(ids.into_iter().map(|id| self.load(id)))
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
     pub async fn load(&self, id: CardId) -> Option<Arc<Card>> {
         trace!("loading card for id: {}", id);
         if let (Some(card), Some(_), Some(_)) = (
@@ -325,20 +573,41 @@ impl CardProvider {
     }
 
     pub async fn save_reviews(&self, reviews: History) {
+        let id = reviews.id();
         self.provider.reviews.save_item(reviews).await;
+        self.notify(CardChange::Modified(id));
     }
 
-    pub async fn save_basecard(&self, card: BaseCard) -> Arc<Card> {
+    pub async fn save_basecard(&self, mut card: BaseCard) -> Arc<Card> {
         let id = card.id();
+
+        if card.created.is_none() {
+            card.created = match self.provider.cards.load_item(id).await {
+                Some(old) => old.created,
+                None => Some(self.time_provider.current_time()),
+            };
+        }
+
         self.provider.cards.save_item(card).await;
         self.invalidate_card(id).await;
+        self.notify(CardChange::Modified(id));
         self.load(id).await.unwrap()
     }
 
+    /// Saves the metadata of a card on its own, for changes that don't touch the card itself.
+    pub async fn save_metadata(&self, meta: Metadata) {
+        let id = meta.id();
+        self.provider.metadata.save_item(meta).await;
+        self.invalidate_card(id).await;
+        self.notify(CardChange::Modified(id));
+    }
+
     pub async fn save_card(&self, card: Card) {
+        let id = card.id();
         self.update_cache(Arc::new(card.clone()));
         self.provider.metadata.save_item(card.meta()).await;
         self.provider.cards.save_item(card.base).await;
+        self.notify(CardChange::Modified(id));
     }
 
     pub fn time_provider(&self) -> TimeGetter {
@@ -352,14 +621,29 @@ impl CardProvider {
                 reviews: Default::default(),
                 dependents: Default::default(),
                 metadata: Default::default(),
+                recall_rates: Default::default(),
+                cache_limit: None,
+                last_used: Default::default(),
+                by_use: Default::default(),
+                clock: 0,
             })),
             time_provider,
             provider,
             recaller,
             check_modified: false,
+            subscribers: Default::default(),
         }
     }
 
+    /// Bounds how many cards are kept in memory, evicting the least recently used ones past the limit.
+    ///
+    /// [`Self::fill_cache`] still loads every card, the limit is applied from the next card that gets cached.
+    pub fn set_cache_limit(&self, limit: Option<usize>) {
+        let mut guard = self.inner.write().unwrap();
+        guard.cache_limit = limit;
+        guard.evict();
+    }
+
     async fn load_uncached(&self, id: CardId) -> Option<Card> {
         trace!("load uncached");
         let raw_card = self.provider.cards.load_item(id).await?;
@@ -439,10 +723,13 @@ impl CardProvider {
 
     async fn load_cached_entry(&self, id: CardId) -> Option<CardCache> {
         trace!("attempting cache load for card: {}", id);
-        let guard = self.inner.read().unwrap();
+        let mut guard = self.inner.write().unwrap();
         trace!("cache size: {}", guard.cards.len());
-        match guard.cards.get(&id) {
-            Some(cached) => Some((*cached).clone()),
+        match guard.cards.get(&id).cloned() {
+            Some(cached) => {
+                guard.touch(id);
+                Some(cached)
+            }
             None => {
                 trace!("cache miss for card: {}", id);
                 None
@@ -488,6 +775,8 @@ impl CardProvider {
         guard.cards.insert(id, cached_card);
         guard.reviews.insert(id, cached_reviews);
         guard.metadata.insert(id, cached_meta);
+        guard.touch(id);
+        guard.evict();
     }
 
     async fn fresh_load(&self, id: CardId) -> Option<Arc<Card>> {
@@ -504,6 +793,61 @@ struct Inner {
     reviews: HashMap<CardId, RevCache>,
     dependents: HashMap<CardId, HashSet<CardId>>,
     metadata: HashMap<CardId, Metadata>,
+    recall_rates: HashMap<CardId, RecallCache>,
+    /// Max amount of cards to keep cached, unbounded if `None`.
+    cache_limit: Option<usize>,
+    /// When each cached card was last used, in ticks of `clock`.
+    last_used: HashMap<CardId, u64>,
+    /// Reverse of `last_used`, so the least recently used card is the first entry.
+    by_use: BTreeMap<u64, CardId>,
+    clock: u64,
+}
+
+impl Inner {
+    fn touch(&mut self, id: CardId) {
+        self.forget(id);
+        self.clock += 1;
+        self.last_used.insert(id, self.clock);
+        self.by_use.insert(self.clock, id);
+    }
+
+    fn forget(&mut self, id: CardId) {
+        if let Some(tick) = self.last_used.remove(&id) {
+            self.by_use.remove(&tick);
+        }
+    }
+
+    /// Evicts the least recently used cards until the cache is within its limit.
+    ///
+    /// The dependents index is kept since it's built from every card that has been loaded.
+    fn evict(&mut self) {
+        let Some(limit) = self.cache_limit else {
+            return;
+        };
+
+        while self.cards.len() > limit {
+            let Some((_, oldest)) = self.by_use.pop_first() else {
+                return;
+            };
+
+            trace!("evicting card from cache: {oldest}");
+            self.cards.remove(&oldest);
+            self.reviews.remove(&oldest);
+            self.metadata.remove(&oldest);
+            self.recall_rates.remove(&oldest);
+            self.last_used.remove(&oldest);
+        }
+    }
+}
+
+/// How long a computed recall rate is reused for.
+const RECALL_CACHE_SECS: u64 = 60;
+
+struct RecallCache {
+    /// Amount of reviews and timestamp of the last review, so a changed history doesn't reuse the cached value.
+    history: (usize, Option<Duration>),
+    bucket: u64,
+    recall_rate: Option<RecallRate>,
 }
 
 #[derive(Clone, Debug)]
@@ -519,3 +863,482 @@ struct RevCache {
 }
 
 type DepCache = HashSet<CardId>;
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use futures::{channel::mpsc::UnboundedReceiver, executor::block_on};
+    use speki_dto::TimeProvider;
+    use speki_provider::MemProvider;
+
+    use super::{CardChange, CardProvider};
+    use crate::{
+        card::RecallRate,
+        collection::{Collection, DynCard},
+        metadata::Difficulty,
+        recall_rate::{History, Recall},
+        testing::{test_app, ControlledTime},
+        App, CardId, Provider, RecallCalc, SimpleRecall,
+    };
+
+    #[test]
+    fn test_load_many() {
+        block_on(async {
+            let (app, _) = test_app();
+            let first = app.add_card("a".to_string(), "b".to_string()).await;
+            let second = app.add_card("c".to_string(), "d".to_string()).await;
+            let missing = CardId::new_v4();
+
+            let mut loaded: Vec<CardId> = app
+                .card_provider
+                .load_many([first, missing, second])
+                .await
+                .iter()
+                .map(|card| card.id())
+                .collect();
+            loaded.sort();
+
+            let mut expected = vec![first, second];
+            expected.sort();
+            assert_eq!(loaded, expected);
+        });
+    }
+
+    #[test]
+    fn test_dependencies() {
+        block_on(async {
+            let (app, _) = test_app();
+            let first = app.add_card("a".to_string(), "b".to_string()).await;
+            let id = app.add_card("e".to_string(), "f".to_string()).await;
+
+            let mut card = app.load_card(id).await.unwrap();
+            card.add_dependency(first).await.unwrap();
+            card.add_dependency(CardId::new_v4()).await.unwrap();
+
+            let card = app.load_card(id).await.unwrap();
+            let deps: Vec<CardId> = card.dependencies().await.iter().map(|c| c.id()).collect();
+            assert_eq!(deps, vec![first]);
+        });
+    }
+
+    #[test]
+    fn test_load_ids_sorted() {
+        block_on(async {
+            let (app, _) = test_app();
+            let mut added = vec![];
+            for i in 0..10 {
+                added.push(app.add_card(i.to_string(), "back".to_string()).await);
+            }
+            added.sort();
+
+            assert_eq!(app.load_cards().await, added);
+            assert_eq!(app.card_provider.load_all_card_ids_sorted().await, added);
+        });
+    }
+
+    #[test]
+    fn test_needs_work_cards() {
+        block_on(async {
+            let (app, _) = test_app();
+            let flagged = app.add_card("a".to_string(), "b".to_string()).await;
+            app.add_card("c".to_string(), "d".to_string()).await;
+            assert!(app.card_provider.needs_work_cards().await.is_empty());
+
+            let mut card = app.load_card(flagged).await.unwrap();
+            card.set_needs_work(true).await;
+            assert!(app.load_card(flagged).await.unwrap().needs_work());
+            assert_eq!(app.card_provider.needs_work_cards().await, vec![flagged]);
+
+            card.set_needs_work(false).await;
+            assert!(app.card_provider.needs_work_cards().await.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_stale_cards() {
+        block_on(async {
+            let (app, time) = test_app();
+            let day = Duration::from_secs(86400);
+            let old = app.add_card("a".to_string(), "b".to_string()).await;
+            let suspended = app.add_card("c".to_string(), "d".to_string()).await;
+            let unreviewed = app.add_card("e".to_string(), "f".to_string()).await;
+            app.submit_reviews(vec![
+                (old, Recall::Perfect, time.current_time()),
+                (suspended, Recall::Perfect, time.current_time()),
+            ])
+            .await
+            .unwrap();
+            app.load_card(suspended)
+                .await
+                .unwrap()
+                .set_suspend(true)
+                .await;
+
+            time.inc(day * 5);
+            let recent = app.add_card("g".to_string(), "h".to_string()).await;
+            app.submit_reviews(vec![(recent, Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+
+            time.inc(day * 5);
+            let provider = &app.card_provider;
+            assert_eq!(provider.stale_cards(day * 7, false).await, vec![old]);
+
+            let mut stale = provider.stale_cards(day * 7, true).await;
+            stale.sort();
+            let mut expected = vec![old, unreviewed];
+            expected.sort();
+            assert_eq!(stale, expected);
+
+            // The cutoff is exclusive.
+            assert_eq!(provider.stale_cards(day * 10, false).await, vec![]);
+            assert_eq!(provider.stale_cards(day * 5, false).await, vec![old]);
+        });
+    }
+
+    #[test]
+    fn test_cards_with_difficulty() {
+        block_on(async {
+            let (app, _) = test_app();
+            let hard = app.add_card("a".to_string(), "b".to_string()).await;
+            let easy = app.add_card("c".to_string(), "d".to_string()).await;
+            app.add_card("e".to_string(), "f".to_string()).await;
+
+            let mut card = app.load_card(hard).await.unwrap();
+            card.set_difficulty(Some(Difficulty::Hard)).await;
+            app.load_card(easy)
+                .await
+                .unwrap()
+                .set_difficulty(Some(Difficulty::Easy))
+                .await;
+
+            let provider = &app.card_provider;
+            assert_eq!(
+                app.load_card(hard).await.unwrap().difficulty(),
+                Some(Difficulty::Hard)
+            );
+            assert_eq!(
+                provider.cards_with_difficulty(Difficulty::Hard).await,
+                vec![hard]
+            );
+            assert_eq!(
+                provider.cards_with_difficulty(Difficulty::Easy).await,
+                vec![easy]
+            );
+            assert!(provider
+                .cards_with_difficulty(Difficulty::Medium)
+                .await
+                .is_empty());
+
+            card.set_difficulty(None).await;
+            assert!(provider
+                .cards_with_difficulty(Difficulty::Hard)
+                .await
+                .is_empty());
+        });
+    }
+
+    #[test]
+    fn test_study_plan() {
+        block_on(async {
+            let (app, _) = test_app();
+            let add = |front: &str| app.add_card(front.to_string(), "back".to_string());
+            let depend = |id: CardId, dep: CardId| {
+                let app = &app;
+                async move {
+                    let mut card = app.load_card(id).await.unwrap();
+                    card.add_dependency(dep).await.unwrap();
+                }
+            };
+
+            let base = add("base").await;
+            let left = add("left").await;
+            let right = add("right").await;
+            let top = add("top").await;
+            let suspended = add("suspended").await;
+            let trivial = app
+                .add_card("trivial".to_string(), crate::card::BackSide::Trivial)
+                .await;
+            let unfinished = app.add_unfinished("unfinished".to_string()).await;
+            app.load_card(suspended)
+                .await
+                .unwrap()
+                .set_suspend(true)
+                .await;
+
+            depend(left, base).await;
+            depend(right, base).await;
+            depend(top, left).await;
+            depend(top, right).await;
+            for dep in [suspended, trivial, unfinished, CardId::new_v4()] {
+                depend(top, dep).await;
+            }
+
+            let plan = app.card_provider.study_plan(top).await;
+            assert_eq!(plan.len(), 3);
+            let pos = |id: CardId| plan.iter().position(|x| *x == id).unwrap();
+            assert!(pos(base) < pos(left));
+            assert!(pos(base) < pos(right));
+
+            assert_eq!(app.card_provider.study_plan(left).await, vec![base]);
+            assert!(app.card_provider.study_plan(base).await.is_empty());
+        });
+    }
+
+    /// Counts how many times a recall rate is actually computed.
+    #[derive(Clone, Default)]
+    struct CountingRecall {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl CountingRecall {
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    impl RecallCalc for CountingRecall {
+        fn recall_rate(&self, reviews: &History, current_unix: Duration) -> Option<RecallRate> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            SimpleRecall.recall_rate(reviews, current_unix)
+        }
+    }
+
+    #[test]
+    fn test_recall_rate_cache() {
+        block_on(async {
+            let time = ControlledTime::default();
+            let recall = CountingRecall::default();
+            let storage = MemProvider::new_with_time(time.clone());
+            let app = App::new(recall.clone(), time.clone(), Provider::new(storage));
+
+            let id = app.add_card("a".to_string(), "b".to_string()).await;
+            app.submit_reviews(vec![(id, Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+            let card = app.load_card(id).await.unwrap();
+
+            let calls = recall.calls();
+            let rate = card.recall_rate();
+            assert_eq!(recall.calls(), calls + 1);
+            time.inc(Duration::from_secs(10));
+            assert_eq!(card.recall_rate(), rate);
+            assert_eq!(recall.calls(), calls + 1);
+
+            // A new time bucket is computed again.
+            time.inc(Duration::from_secs(60));
+            let rate = card.recall_rate();
+            assert_eq!(recall.calls(), calls + 2);
+            assert_eq!(card.recall_rate(), rate);
+            assert_eq!(recall.calls(), calls + 2);
+
+            // So is a changed history.
+            app.submit_reviews(vec![(id, Recall::None, time.current_time())])
+                .await
+                .unwrap();
+            let card = app.load_card(id).await.unwrap();
+            let calls = recall.calls();
+            assert_ne!(card.recall_rate(), rate);
+            assert_eq!(recall.calls(), calls + 1);
+        });
+    }
+
+    #[test]
+    fn test_remove_card_from_all_collections() {
+        block_on(async {
+            let (app, _) = test_app();
+            let deleted = app.add_card("a".to_string(), "b".to_string()).await;
+            let kept = app.add_card("c".to_string(), "d".to_string()).await;
+
+            let mut first = Collection::new("first".to_string());
+            first.dyncards = vec![
+                DynCard::Card(deleted),
+                DynCard::Card(kept),
+                DynCard::Limit(Box::new(DynCard::Dependents(deleted)), 3),
+            ];
+            let mut second = Collection::new("second".to_string());
+            second.dyncards = vec![DynCard::Instances(deleted)];
+            app.provider.collections.save(first.clone()).await;
+            app.provider.collections.save(second.clone()).await;
+
+            app.load_card(deleted).await.unwrap().delete_card().await;
+
+            let load = |id| {
+                let app = &app;
+                async move { app.provider.collections.load(id).await.unwrap().dyncards }
+            };
+            assert_eq!(load(first.id).await, vec![DynCard::Card(kept)]);
+            assert!(load(second.id).await.is_empty());
+
+            // Also available without deleting the card.
+            app.remove_card_from_all_collections(kept).await;
+            assert!(load(first.id).await.is_empty());
+            assert!(app.load_card(kept).await.is_some());
+        });
+    }
+
+    fn received(rx: &mut UnboundedReceiver<CardChange>) -> Vec<CardChange> {
+        let mut changes = vec![];
+        while let Ok(Some(change)) = rx.try_next() {
+            changes.push(change);
+        }
+        changes.dedup();
+        changes
+    }
+
+    #[test]
+    fn test_subscribe() {
+        block_on(async {
+            let (app, time) = test_app();
+            let mut rx = app.card_provider.subscribe();
+            let dropped = app.card_provider.subscribe();
+
+            let id = app.add_card("a".to_string(), "b".to_string()).await;
+            assert_eq!(received(&mut rx), vec![CardChange::Modified(id)]);
+
+            drop(dropped);
+            app.submit_reviews(vec![(id, Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+            assert_eq!(received(&mut rx), vec![CardChange::Modified(id)]);
+            assert_eq!(app.card_provider.subscribers.read().unwrap().len(), 1);
+
+            app.load_card(id).await.unwrap().delete_card().await;
+            assert_eq!(received(&mut rx), vec![CardChange::Deleted(id)]);
+        });
+    }
+
+    #[test]
+    fn test_roots_leaves_isolated() {
+        block_on(async {
+            let time = ControlledTime::default();
+            let storage = MemProvider::new_with_time(time.clone());
+            let app = App::new(SimpleRecall, time.clone(), Provider::new(storage.clone()));
+            let dependent = app.add_card("a".to_string(), "b".to_string()).await;
+            let dependency = app.add_card("c".to_string(), "d".to_string()).await;
+            let isolated = app.add_card("e".to_string(), "f".to_string()).await;
+            app.load_card(dependent)
+                .await
+                .unwrap()
+                .add_dependency(dependency)
+                .await
+                .unwrap();
+
+            let sorted = |mut ids: Vec<CardId>| {
+                ids.sort();
+                ids
+            };
+
+            // A fresh app on the same storage starts out with nothing cached.
+            let app = App::new(SimpleRecall, time, Provider::new(storage));
+            let provider = &app.card_provider;
+            assert_eq!(provider.leaves().await, sorted(vec![dependent, isolated]));
+            assert_eq!(provider.roots().await, sorted(vec![dependency, isolated]));
+            assert_eq!(provider.isolated().await, vec![isolated]);
+        });
+    }
+
+    fn cached_ids(app: &App) -> Vec<CardId> {
+        let mut ids: Vec<CardId> = app
+            .card_provider
+            .inner
+            .read()
+            .unwrap()
+            .cards
+            .keys()
+            .copied()
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_cache_limit() {
+        block_on(async {
+            let (app, _) = test_app();
+            let a = app.add_card("a".to_string(), "b".to_string()).await;
+            let b = app.add_card("c".to_string(), "d".to_string()).await;
+            let c = app.add_card("e".to_string(), "f".to_string()).await;
+            app.load_card(c)
+                .await
+                .unwrap()
+                .add_dependency(b)
+                .await
+                .unwrap();
+
+            let provider = &app.card_provider;
+            provider.set_cache_limit(Some(2));
+            assert!(cached_ids(&app).len() <= 2);
+
+            provider.load(a).await.unwrap();
+            provider.load(b).await.unwrap();
+            provider.load(a).await.unwrap();
+            provider.load(c).await.unwrap();
+
+            // b was used least recently, so it's the one evicted.
+            let mut expected = vec![a, c];
+            expected.sort();
+            assert_eq!(cached_ids(&app), expected);
+            {
+                let guard = provider.inner.read().unwrap();
+                assert!(guard.last_used.len() <= 2);
+                assert_eq!(guard.last_used.len(), guard.by_use.len());
+            }
+
+            // Evicted cards keep their dependents and load again from storage.
+            assert_eq!(provider.dependent_ids(b), [c].into());
+            assert_eq!(provider.load(b).await.unwrap().print().await, "c");
+            assert!(cached_ids(&app).contains(&b));
+
+            provider.set_cache_limit(None);
+            provider.load(a).await.unwrap();
+            provider.load(c).await.unwrap();
+            assert_eq!(cached_ids(&app).len(), 3);
+        });
+    }
+
+    #[test]
+    fn test_display_name() {
+        block_on(async {
+            let (app, _) = test_app();
+            let kept = app.add_card("kept".to_string(), "b".to_string()).await;
+            let deleted = app.add_card("deleted".to_string(), "d".to_string()).await;
+            app.load_card(deleted).await.unwrap().delete_card().await;
+
+            let provider = &app.card_provider;
+            assert_eq!(provider.display_name(kept).await, "kept");
+            assert_eq!(
+                provider.display_name(deleted).await,
+                CardProvider::DELETED_CARD_NAME
+            );
+            assert_eq!(
+                provider.display_name(CardId::new_v4()).await,
+                CardProvider::DELETED_CARD_NAME
+            );
+        });
+    }
+
+    #[test]
+    fn test_subscribe_metadata_change() {
+        block_on(async {
+            let (app, _) = test_app();
+            let id = app.add_card("a".to_string(), "b".to_string()).await;
+            let mut rx = app.card_provider.subscribe();
+
+            let mut card = app.load_card(id).await.unwrap();
+            card.set_suspend(true).await;
+            assert_eq!(received(&mut rx), vec![CardChange::Modified(id)]);
+            assert!(app.load_card(id).await.unwrap().is_suspended());
+
+            card.set_priority(3).await;
+            assert_eq!(received(&mut rx), vec![CardChange::Modified(id)]);
+        });
+    }
+}
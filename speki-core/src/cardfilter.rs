@@ -50,6 +50,40 @@ impl Display for NumOrd {
     }
 }
 
+/// How a card's prerequisites (its recursive dependencies) gate whether it passes the filter.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum PrereqPolicy {
+    /// Every prerequisite must be finished and have been reviewed.
+    Strict,
+    /// Prerequisites are ignored.
+    Lenient,
+    /// The lowest recall rate among the prerequisites must be at least this value.
+    Threshold(f32),
+}
+
+impl PrereqPolicy {
+    pub async fn allows(&self, card: &Card) -> bool {
+        match self {
+            PrereqPolicy::Lenient => true,
+            PrereqPolicy::Threshold(threshold) => card.min_rec_recall_rate().await >= *threshold,
+            PrereqPolicy::Strict => {
+                if card.min_rec_recall_rate().await <= 0.0 {
+                    return false;
+                }
+
+                for dep in card.all_dependencies().await {
+                    match card.card_provider().load(dep).await {
+                        Some(dep) if dep.is_finished() => {}
+                        _ => return false,
+                    }
+                }
+
+                true
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct ItemData {
     last_modified: Duration,
@@ -68,6 +102,45 @@ pub struct CardFilter {
     pub suspended: Option<bool>,
     pub pending: Option<bool>,
     pub lapses: Option<NumOp>,
+    #[serde(default)]
+    pub trivial: Option<bool>,
+    /// Evaluate the recall rate this far into the future, to review cards before they're due.
+    #[serde(default)]
+    pub ahead: Option<Duration>,
+    #[serde(default)]
+    pub prereq: Option<PrereqPolicy>,
+    /// Max amount of never-reviewed cards to introduce per day.
+    ///
+    /// Not checked by [`CardFilter::filter`] since it depends on the other cards, see [`crate::App::filter_cards`].
+    #[serde(default)]
+    pub new_cards_per_day: Option<usize>,
+    /// Only keep one attribute card per instance, so sibling attributes aren't reviewed in the same session.
+    ///
+    /// Not checked by [`CardFilter::filter`], see [`crate::App::filter_cards`].
+    #[serde(default)]
+    pub bury_siblings: bool,
+}
+
+/// Which cards to show based on where they were created.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SourceFilter {
+    /// Cards created in this provider.
+    LocalOnly,
+    /// Cards that came in from another provider through syncing.
+    RemoteOnly,
+    #[default]
+    Both,
+}
+
+impl SourceFilter {
+    pub fn matches(&self, source: ModifiedSource) -> bool {
+        matches!(
+            (self, source),
+            (Self::Both, _)
+                | (Self::LocalOnly, ModifiedSource::Local)
+                | (Self::RemoteOnly, ModifiedSource::External { .. })
+        )
+    }
 }
 
 impl CardFilter {
@@ -81,10 +154,23 @@ impl CardFilter {
             suspended,
             pending,
             lapses,
+            trivial,
+            ahead,
+            prereq,
+            new_cards_per_day: _,
+            bury_siblings: _,
         } = self.clone();
 
-        if let Some(NumOp { ord, num }) = recall {
-            let recall = card.recall_rate().unwrap_or_default();
+        // Manually scheduled cards are due based on their schedule rather than their recall rate.
+        let scheduled_due = card.scheduled_due(ahead.unwrap_or_default());
+        if recall.is_some() && scheduled_due == Some(false) {
+            return false;
+        }
+
+        if let (Some(NumOp { ord, num }), None) = (recall, scheduled_due) {
+            let recall = card
+                .recall_rate_ahead(ahead.unwrap_or_default())
+                .unwrap_or_default();
 
             match ord {
                 MyNumOrd::Equal => {
@@ -181,6 +267,11 @@ impl CardFilter {
             if flag != card.is_suspended() {
                 return false;
             }
+
+            // Filters that leave out suspended cards also leave out snoozed ones.
+            if !flag && card.is_snoozed(ahead.unwrap_or_default()) {
+                return false;
+            }
         }
 
         if let Some(flag) = pending {
@@ -189,6 +280,18 @@ impl CardFilter {
             }
         }
 
+        if let Some(flag) = trivial {
+            if flag != card.is_trivial() {
+                return false;
+            }
+        }
+
+        if let Some(policy) = prereq {
+            if !policy.allows(&card).await {
+                return false;
+            }
+        }
+
         true
     }
 }
@@ -236,3 +339,124 @@ impl Item for FilterItem {
         self.source = source;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::executor::block_on;
+    use speki_dto::{ModifiedSource, TimeProvider};
+    use uuid::Uuid;
+
+    use super::{CardFilter, MyNumOrd, NumOp, PrereqPolicy, SourceFilter};
+    use crate::{recall_rate::Recall, testing::test_app, App, CardId};
+
+    #[test]
+    fn test_source_filter() {
+        let local = ModifiedSource::Local;
+        let remote = ModifiedSource::External {
+            from: Uuid::new_v4(),
+            inserted: Duration::from_secs(10),
+        };
+
+        assert!(SourceFilter::Both.matches(local));
+        assert!(SourceFilter::Both.matches(remote));
+        assert!(SourceFilter::LocalOnly.matches(local));
+        assert!(!SourceFilter::LocalOnly.matches(remote));
+        assert!(!SourceFilter::RemoteOnly.matches(local));
+        assert!(SourceFilter::RemoteOnly.matches(remote));
+    }
+
+    async fn depending_on(app: &App, dependency: CardId) -> CardId {
+        let id = app.add_card("front".to_string(), "back".to_string()).await;
+        let mut card = app.load_card(id).await.unwrap();
+        card.add_dependency(dependency).await.unwrap();
+        id
+    }
+
+    #[test]
+    fn test_prereq_policy() {
+        block_on(async {
+            let (app, time) = test_app();
+            let known = app.add_card("known".to_string(), "back".to_string()).await;
+            let unknown = app
+                .add_card("unknown".to_string(), "back".to_string())
+                .await;
+            let unfinished = app.add_unfinished("unfinished".to_string()).await;
+            app.submit_reviews(vec![
+                (known, Recall::Perfect, time.current_time()),
+                (unfinished, Recall::Perfect, time.current_time()),
+            ])
+            .await
+            .unwrap();
+
+            let on_known = depending_on(&app, known).await;
+            let on_unknown = depending_on(&app, unknown).await;
+            let on_unfinished = depending_on(&app, unfinished).await;
+
+            let allowed = |policy: PrereqPolicy| {
+                let app = &app;
+                async move {
+                    let mut allowed = vec![];
+                    for id in [on_known, on_unknown, on_unfinished] {
+                        let card = app.load_card(id).await.unwrap();
+                        allowed.push(policy.allows(&card).await);
+                    }
+                    allowed
+                }
+            };
+
+            assert_eq!(allowed(PrereqPolicy::Lenient).await, [true, true, true]);
+            assert_eq!(allowed(PrereqPolicy::Strict).await, [true, false, false]);
+            assert_eq!(
+                allowed(PrereqPolicy::Threshold(0.5)).await,
+                [true, false, true]
+            );
+
+            let filter = CardFilter {
+                prereq: Some(PrereqPolicy::Strict),
+                ..Default::default()
+            };
+            let ids: Vec<CardId> = app
+                .cards_filtered(filter)
+                .await
+                .iter()
+                .map(|card| card.id())
+                .collect();
+            assert!(ids.contains(&on_known));
+            assert!(!ids.contains(&on_unknown));
+            assert!(!ids.contains(&on_unfinished));
+        });
+    }
+
+    #[test]
+    fn test_ahead() {
+        block_on(async {
+            let (app, time) = test_app();
+            let id = app.add_card("front".to_string(), "back".to_string()).await;
+            app.submit_reviews(vec![(id, Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+            time.inc(Duration::from_secs(3600));
+
+            let card = app.card_provider.load(id).await.unwrap();
+            let week = Duration::from_secs(86400 * 7);
+            assert_eq!(
+                card.recall_rate_ahead(Duration::default()),
+                card.recall_rate()
+            );
+            assert!(card.recall_rate_ahead(week).unwrap() < card.recall_rate().unwrap());
+
+            let due_within = |ahead: Option<Duration>| CardFilter {
+                recall: Some(NumOp {
+                    num: card.recall_rate_ahead(week).unwrap() + 0.01,
+                    ord: MyNumOrd::Less,
+                }),
+                ahead,
+                ..Default::default()
+            };
+            assert!(!due_within(None).filter(card.clone()).await);
+            assert!(due_within(Some(week)).filter(card).await);
+        });
+    }
+}
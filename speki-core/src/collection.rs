@@ -66,8 +66,9 @@ impl Collection {
 
         for card in &out {
             for dep in card.all_dependencies().await {
-                let card = provider.load(dep).await.unwrap();
-                dependencies.insert(card);
+                if let Some(card) = provider.load(dep).await {
+                    dependencies.insert(card);
+                }
             }
         }
 
@@ -84,9 +85,41 @@ pub enum DynCard {
     Dependents(CardId),
     RecDependents(CardId),
     Collection(CollectionId),
+    /// At most this many cards from the inner entry, picked by lowest id so the result is stable.
+    Limit(Box<DynCard>, usize),
+    /// A pseudorandom selection of this many cards from the inner entry, the same seed gives the same selection.
+    Sample(Box<DynCard>, usize, u64),
 }
 
 impl DynCard {
+    /// Returns the card this entry is based on, if any.
+    pub fn card_id(&self) -> Option<CardId> {
+        match self {
+            DynCard::Card(id) => Some(*id),
+            DynCard::Instances(id) => Some(*id),
+            DynCard::Dependents(id) => Some(*id),
+            DynCard::RecDependents(id) => Some(*id),
+            DynCard::Collection(_) => None,
+            DynCard::Limit(inner, _) | DynCard::Sample(inner, _, _) => inner.card_id(),
+        }
+    }
+
+    pub fn replace_ref(&mut self, from: CardId, to: CardId) {
+        match self {
+            DynCard::Card(id)
+            | DynCard::Instances(id)
+            | DynCard::Dependents(id)
+            | DynCard::RecDependents(id) => {
+                if *id == from {
+                    *id = to;
+                }
+            }
+            DynCard::Collection(_) => {}
+            DynCard::Limit(inner, _) | DynCard::Sample(inner, _, _) => inner.replace_ref(from, to),
+        }
+    }
+
+    #[async_recursion(?Send)]
     pub async fn evaluate(
         &self,
         provider: CardProvider,
@@ -99,12 +132,11 @@ impl DynCard {
                 };
                 col.expand(provider.clone(), seen_cols).await
             }
-            DynCard::Card(id) => {
-                let card = provider.load(*id).await.unwrap();
-                vec![card]
-            }
+            DynCard::Card(id) => provider.load(*id).await.into_iter().collect(),
             DynCard::Instances(id) => {
-                let card = provider.load(*id).await.unwrap();
+                let Some(card) = provider.load(*id).await else {
+                    return vec![];
+                };
                 let mut output = vec![];
 
                 for card in card.dependents().await {
@@ -115,29 +147,43 @@ impl DynCard {
 
                 output
             }
-            DynCard::Dependents(id) => provider
-                .load(*id)
-                .await
-                .unwrap()
-                .dependents()
-                .await
-                .into_iter()
-                .collect(),
+            DynCard::Dependents(id) => match provider.load(*id).await {
+                Some(card) => card.dependents().await.into_iter().collect(),
+                None => vec![],
+            },
             DynCard::RecDependents(id) => {
-                let ids = provider.load(*id).await.unwrap().all_dependents().await;
-                let mut out = vec![];
-
-                for id in ids {
-                    let card = provider.load(id).await.unwrap();
-                    out.push(card);
-                }
+                let Some(card) = provider.load(*id).await else {
+                    return vec![];
+                };
 
-                out
+                provider.load_many(card.all_dependents().await).await
+            }
+            DynCard::Limit(inner, max) => {
+                let mut cards = inner.evaluate(provider, seen_cols).await;
+                cards.sort_by_key(|card| card.id());
+                cards.truncate(*max);
+                cards
+            }
+            DynCard::Sample(inner, max, seed) => {
+                let mut cards = inner.evaluate(provider, seen_cols).await;
+                cards.sort_by_key(|card| sample_key(card.id(), *seed));
+                cards.truncate(*max);
+                cards
             }
         }
     }
 }
 
+/// Seeded pseudorandom sort key, based on splitmix64.
+fn sample_key(id: CardId, seed: u64) -> u64 {
+    let (high, low) = id.as_u64_pair();
+    let mut x = high ^ low.rotate_left(32) ^ seed;
+    x = x.wrapping_add(0x9E3779B97F4A7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D049BB133111EB);
+    x ^ (x >> 31)
+}
+
 impl Item for Collection {
     fn deleted(&self) -> bool {
         self.deleted
@@ -171,3 +217,58 @@ impl Item for Collection {
         self.source = source;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use futures::executor::block_on;
+
+    use super::DynCard;
+    use crate::{card::CardId, testing::test_app, App};
+
+    async fn evaluate(app: &App, dyncard: DynCard) -> Vec<CardId> {
+        dyncard
+            .evaluate(app.card_provider(), Default::default())
+            .await
+            .iter()
+            .map(|card| card.id())
+            .collect()
+    }
+
+    #[test]
+    fn test_limit_and_sample() {
+        block_on(async {
+            let (app, _) = test_app();
+            let root = app.add_card("root".to_string(), "back".to_string()).await;
+            let mut dependents = BTreeSet::new();
+            for i in 0..10 {
+                let id = app.add_card(i.to_string(), "back".to_string()).await;
+                let mut card = app.load_card(id).await.unwrap();
+                card.add_dependency(root).await.unwrap();
+                dependents.insert(id);
+            }
+            let all = || Box::new(DynCard::Dependents(root));
+
+            let limited = evaluate(&app, DynCard::Limit(all(), 3)).await;
+            let lowest: Vec<CardId> = dependents.iter().take(3).copied().collect();
+            assert_eq!(limited, lowest);
+            assert_eq!(evaluate(&app, DynCard::Limit(all(), 20)).await.len(), 10);
+
+            let sample = evaluate(&app, DynCard::Sample(all(), 3, 1)).await;
+            assert_eq!(sample.len(), 3);
+            assert!(sample.iter().all(|id| dependents.contains(id)));
+            assert_eq!(evaluate(&app, DynCard::Sample(all(), 3, 1)).await, sample);
+
+            let mut samples = BTreeSet::new();
+            for seed in 0..5 {
+                let mut sample = evaluate(&app, DynCard::Sample(all(), 3, seed)).await;
+                sample.sort();
+                samples.insert(sample);
+            }
+            assert!(samples.len() > 1);
+
+            assert_eq!(DynCard::Limit(all(), 3).card_id(), Some(root));
+        });
+    }
+}
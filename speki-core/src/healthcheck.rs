@@ -2,6 +2,7 @@ use crate::card_provider::CardProvider;
 
 pub async fn healthcheck(provider: CardProvider) {
     check_dependencies(&provider).await;
+    check_valid(&provider).await;
 }
 
 async fn check_dependencies(provider: &CardProvider) {
@@ -14,3 +15,11 @@ async fn check_dependencies(provider: &CardProvider) {
         }
     }
 }
+
+async fn check_valid(provider: &CardProvider) {
+    for card in provider.load_all().await {
+        if let Err(e) = card.validate().await {
+            tracing::error!("{card} is invalid: {e}");
+        }
+    }
+}
@@ -1,14 +1,22 @@
-use std::{collections::HashMap, fmt::Debug, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::Debug,
+    sync::Arc,
+    time::Duration,
+};
 
 use audio::Audio;
-use card::{BackSide, BaseCard, CardId, RecallRate};
+use card::{BackSide, BaseCard, CType, CardId, RecallRate};
+pub use card_provider::CardChange;
 use card_provider::CardProvider;
-use cardfilter::{CardFilter, FilterItem};
+use cardfilter::{CardFilter, FilterItem, SourceFilter};
+use chrono::{DateTime, NaiveDate};
 use collection::{Collection, CollectionId, DynCard};
 use dioxus_logger::tracing::info;
 use eyre::Result;
 use metadata::Metadata;
-use recall_rate::History;
+use recall_rate::{History, Recall, Review};
+use session::{ReviewSession, ReviewSettings};
 use speki_dto::{SpekiProvider, TimeProvider};
 use tracing::trace;
 
@@ -22,6 +30,9 @@ mod common;
 pub mod healthcheck;
 pub mod metadata;
 pub mod recall_rate;
+pub mod session;
+#[cfg(test)]
+mod testing;
 
 pub use attribute::{Attribute, AttributeDTO, AttributeId};
 pub use card::{
@@ -29,7 +40,7 @@ pub use card::{
     StatementCard, UnfinishedCard,
 };
 pub use common::current_time;
-pub use omtrent::TimeStamp;
+pub use omtrent::{TimeRange, TimeStamp};
 pub use recall_rate::SimpleRecall;
 
 pub trait RecallCalc {
@@ -42,6 +53,12 @@ pub struct CollectionProvider {
 }
 
 impl CollectionProvider {
+    pub fn new(provider: impl SpekiProvider<Collection> + 'static + Send) -> Self {
+        Self {
+            inner: Arc::new(Box::new(provider)),
+        }
+    }
+
     pub async fn save(&self, collection: Collection) {
         self.inner.save_item(collection).await
     }
@@ -84,6 +101,39 @@ pub struct Provider {
     pub metadata: Arc<Box<dyn SpekiProvider<Metadata>>>,
     pub cardfilter: Arc<Box<dyn SpekiProvider<FilterItem>>>,
     pub audios: Arc<Box<dyn SpekiProvider<Audio>>>,
+    pub sessions: Arc<Box<dyn SpekiProvider<ReviewSession>>>,
+    pub settings: Arc<Box<dyn SpekiProvider<ReviewSettings>>>,
+}
+
+impl Provider {
+    /// Uses the same backend for every item type.
+    pub fn new<P>(provider: P) -> Self
+    where
+        P: SpekiProvider<BaseCard>
+            + SpekiProvider<History>
+            + SpekiProvider<AttributeDTO>
+            + SpekiProvider<Collection>
+            + SpekiProvider<Metadata>
+            + SpekiProvider<FilterItem>
+            + SpekiProvider<Audio>
+            + SpekiProvider<ReviewSession>
+            + SpekiProvider<ReviewSettings>
+            + Clone
+            + 'static
+            + Send,
+    {
+        Self {
+            cards: Arc::new(Box::new(provider.clone())),
+            reviews: Arc::new(Box::new(provider.clone())),
+            attrs: Arc::new(Box::new(provider.clone())),
+            collections: CollectionProvider::new(provider.clone()),
+            metadata: Arc::new(Box::new(provider.clone())),
+            cardfilter: Arc::new(Box::new(provider.clone())),
+            audios: Arc::new(Box::new(provider.clone())),
+            sessions: Arc::new(Box::new(provider.clone())),
+            settings: Arc::new(Box::new(provider)),
+        }
+    }
 }
 
 pub type Recaller = Arc<Box<dyn RecallCalc + Send>>;
@@ -103,45 +153,16 @@ impl Debug for App {
 }
 
 impl App {
-    pub fn new<A, B, C, D, E, F, G, H, I>(
-        recall_calc: A,
-        time_provider: B,
-        card_provider: C,
-        history_provider: D,
-        attr_provider: E,
-        collections_provider: F,
-        meta_provider: G,
-        filter_provider: H,
-        audio_provider: I,
-    ) -> Self
+    pub fn new<A, B>(recall_calc: A, time_provider: B, provider: Provider) -> Self
     where
         A: RecallCalc + 'static + Send,
         B: TimeProvider + 'static + Send,
-        C: SpekiProvider<BaseCard> + 'static + Send,
-        D: SpekiProvider<History> + 'static + Send,
-        E: SpekiProvider<AttributeDTO> + 'static + Send,
-        F: SpekiProvider<Collection> + 'static + Send,
-        G: SpekiProvider<Metadata> + 'static + Send,
-        H: SpekiProvider<FilterItem> + 'static + Send,
-        I: SpekiProvider<Audio> + 'static + Send,
     {
         info!("initialtize app");
 
         let time_provider: TimeGetter = Arc::new(Box::new(time_provider));
         let recaller: Recaller = Arc::new(Box::new(recall_calc));
 
-        let provider = Provider {
-            cards: Arc::new(Box::new(card_provider)),
-            reviews: Arc::new(Box::new(history_provider)),
-            attrs: Arc::new(Box::new(attr_provider)),
-            collections: CollectionProvider {
-                inner: Arc::new(Box::new(collections_provider)),
-            },
-            metadata: Arc::new(Box::new(meta_provider)),
-            cardfilter: Arc::new(Box::new(filter_provider)),
-            audios: Arc::new(Box::new(audio_provider)),
-        };
-
         let card_provider =
             CardProvider::new(provider.clone(), time_provider.clone(), recaller.clone());
 
@@ -169,6 +190,13 @@ impl App {
         self.card_provider.load_all().await
     }
 
+    /// Loads the cards created locally, the ones synced in from elsewhere, or both.
+    pub async fn load_cards_from(&self, source: SourceFilter) -> Vec<Arc<Card>> {
+        self.card_provider
+            .filtered_load(|card: Arc<Card>| async move { source.matches(card.base.source) })
+            .await
+    }
+
     pub async fn save_card_not_reviews(&self, card: Card) {
         self.card_provider.save_card(card).await;
     }
@@ -193,17 +221,27 @@ impl App {
     }
 
     pub async fn load_cards(&self) -> Vec<CardId> {
-        self.card_provider.load_all_card_ids().await
+        self.card_provider.load_all_card_ids_sorted().await
     }
 
+    /// Saves every card again, cards that don't pass validation are left as they are.
     pub async fn load_and_persist(&self) {
         for card in self.load_all_cards().await {
-            Arc::unwrap_or_clone(card).persist().await;
+            if let Err(e) = Arc::unwrap_or_clone(card).persist().await {
+                tracing::error!("{e}");
+            }
         }
     }
 
     pub async fn cards_filtered(&self, filter: CardFilter) -> Vec<Arc<Card>> {
         let cards = self.load_all_cards().await;
+        self.filter_cards(cards, &filter).await
+    }
+
+    /// Keeps the given cards that pass the filter, with no more new cards than the filter allows per day.
+    ///
+    /// Attribute siblings are buried as well if the filter asks for it, and the cards are ordered by [`Self::order_by_priority`].
+    pub async fn filter_cards(&self, cards: Vec<Arc<Card>>, filter: &CardFilter) -> Vec<Arc<Card>> {
         let mut ids = vec![];
 
         for card in cards {
@@ -211,9 +249,168 @@ impl App {
                 ids.push(card);
             }
         }
+
+        let ids = match filter.new_cards_per_day {
+            Some(per_day) => self.limit_new_cards(ids, per_day).await,
+            None => ids,
+        };
+
+        let mut ids = if filter.bury_siblings {
+            self.bury_siblings(ids).await
+        } else {
+            ids
+        };
+
+        Self::order_by_priority(&mut ids);
         ids
     }
 
+    /// Projects how many cards become due on each of the next `days` days, assuming no reviews are done.
+    ///
+    /// A card counts as due on a day when it passes the filter evaluated that far [`CardFilter::ahead`].
+    /// The first entry is what's due today, including the backlog, the others only count cards that newly became due.
+    pub async fn forecast_workload(&self, filter: CardFilter, days: u32) -> Vec<usize> {
+        let cards = self.load_all_cards().await;
+        let mut due: HashSet<CardId> = HashSet::new();
+        let mut forecast = vec![];
+
+        for day in 0..days {
+            let mut filter = filter.clone();
+            filter.ahead = Some(Duration::from_secs(day as u64 * 86400));
+
+            let mut newly_due = 0;
+            for card in &cards {
+                if !due.contains(&card.id()) && filter.filter(card.clone()).await {
+                    due.insert(card.id());
+                    newly_due += 1;
+                }
+            }
+
+            forecast.push(newly_due);
+        }
+
+        forecast
+    }
+
+    /// Moves higher priority cards ahead, separately among the previously reviewed cards and the new ones.
+    ///
+    /// Each group keeps the positions it had and cards with the same priority keep their order,
+    /// so nothing moves when no priorities are set.
+    pub fn order_by_priority(cards: &mut [Arc<Card>]) {
+        for pending in [false, true] {
+            let positions: Vec<usize> = (0..cards.len())
+                .filter(|&idx| cards[idx].is_pending() == pending)
+                .collect();
+
+            let mut group: Vec<Arc<Card>> =
+                positions.iter().map(|&idx| cards[idx].clone()).collect();
+            group.sort_by_key(|card| std::cmp::Reverse(card.priority()));
+
+            for (idx, card) in positions.into_iter().zip(group) {
+                cards[idx] = card;
+            }
+        }
+    }
+
+    /// Keeps only the first attribute card of each instance, the sibling attributes are left for a later session.
+    ///
+    /// Once an attribute card has been reviewed today its siblings stay buried for the rest of the day.
+    pub async fn bury_siblings(&self, cards: Vec<Arc<Card>>) -> Vec<Arc<Card>> {
+        let day_start = self.day_start();
+        let mut reviewed_today: BTreeMap<CardId, BTreeSet<CardId>> = BTreeMap::new();
+
+        for card in self.load_all_cards().await {
+            if let CardType::Attribute(attr) = card.card_type() {
+                if card
+                    .history()
+                    .last()
+                    .is_some_and(|review| review.timestamp >= day_start)
+                {
+                    reviewed_today
+                        .entry(attr.instance)
+                        .or_default()
+                        .insert(card.id());
+                }
+            }
+        }
+
+        let mut seen_instances = BTreeSet::new();
+
+        cards
+            .into_iter()
+            .filter(|card| match card.card_type() {
+                CardType::Attribute(attr) => match reviewed_today.get(&attr.instance) {
+                    Some(reviewed) => reviewed.contains(&card.id()),
+                    None => seen_instances.insert(attr.instance),
+                },
+                _ => true,
+            })
+            .collect()
+    }
+
+    /// Midnight UTC of the current day.
+    fn day_start(&self) -> Duration {
+        let now = self.time_provider.current_time();
+        Duration::from_secs(now.as_secs() - now.as_secs() % 86400)
+    }
+
+    /// Drops never-reviewed cards so that no more than `per_day` new cards get introduced today.
+    ///
+    /// Days start at midnight UTC.
+    pub async fn limit_new_cards(&self, cards: Vec<Arc<Card>>, per_day: usize) -> Vec<Arc<Card>> {
+        let day_start = self.day_start();
+
+        let introduced_today = self
+            .load_all_cards()
+            .await
+            .into_iter()
+            .filter(|card| {
+                card.first_reviewed_at()
+                    .is_some_and(|timestamp| timestamp >= day_start)
+            })
+            .count();
+
+        let mut remaining = per_day.saturating_sub(introduced_today);
+
+        cards
+            .into_iter()
+            .filter(|card| {
+                if !card.is_pending() {
+                    true
+                } else if remaining > 0 {
+                    remaining -= 1;
+                    true
+                } else {
+                    false
+                }
+            })
+            .collect()
+    }
+
+    /// Amount of reviews done on each of the last `days_back` days, including today.
+    ///
+    /// Days are in UTC, days without reviews are included with a count of 0.
+    pub async fn review_activity(&self, days_back: u32) -> BTreeMap<NaiveDate, usize> {
+        let today = utc_date(self.time_provider.current_time());
+        let mut activity: BTreeMap<NaiveDate, usize> = today
+            .iter_days()
+            .rev()
+            .take(days_back as usize)
+            .map(|day| (day, 0))
+            .collect();
+
+        for history in self.provider.reviews.load_all().await.into_values() {
+            for review in history.inner() {
+                let day = utc_date(review.timestamp);
+                if let Some(count) = activity.get_mut(&day) {
+                    *count += 1;
+                }
+            }
+        }
+
+        activity
+    }
+
     pub async fn add_class(
         &self,
         front: String,
@@ -267,6 +464,28 @@ impl App {
         self.card_provider().save_basecard(base).await.id()
     }
 
+    /// Adds a card from just a front and an optional back, for capturing cards quickly.
+    ///
+    /// With a back it's always a normal card, otherwise `default_type` decides what kind of card the front becomes.
+    pub async fn quick_add(
+        &self,
+        front: String,
+        back: Option<String>,
+        default_type: CType,
+    ) -> Result<CardId> {
+        if let Some(back) = back {
+            return Ok(self.add_card(front, back).await);
+        }
+
+        let base = match default_type {
+            CType::Unfinished => BaseCard::new(UnfinishedCard { front }),
+            CType::Statement => BaseCard::new(StatementCard { front, truth: None }),
+            ty => eyre::bail!("can't add a {ty:?} card without a backside"),
+        };
+
+        Ok(self.card_provider.save_basecard(base).await.id())
+    }
+
     pub async fn set_class(&self, card_id: CardId, class: CardId) -> Result<()> {
         let card = self.card_provider.load(card_id).await.unwrap();
 
@@ -275,97 +494,1898 @@ impl App {
             back: card.back_side().map(ToOwned::to_owned),
             class,
         };
-        Arc::unwrap_or_clone(card).into_type(instance).await;
+        Arc::unwrap_or_clone(card).into_type(instance).await?;
         Ok(())
     }
 
-    pub async fn load_class_cards(&self) -> Vec<Arc<Card>> {
-        self.load_all_cards()
-            .await
-            .into_iter()
-            .filter(|card| card.is_class())
-            .collect()
-    }
-}
+    /// Creates a copy of the card with a new id.
+    ///
+    /// Only the shareable part of the card is copied, the duplicate starts out with no reviews and default metadata.
+    pub async fn duplicate_card(&self, id: CardId) -> Result<CardId> {
+        let Some(card) = self.card_provider.load(id).await else {
+            eyre::bail!("couldn't find card: {id}");
+        };
 
-pub async fn as_graph(app: &App) -> String {
-    graphviz::export(app).await
-}
+        let mut base = BaseCard::new(card.base.ty.clone());
+        base.dependencies = card.base.dependencies.clone();
+        base.front_audio = card.base.front_audio;
+        base.back_audio = card.base.back_audio;
 
-mod graphviz {
-    use std::collections::BTreeSet;
+        let new_id = self.card_provider.save_basecard(base).await.id();
+        info!("duplicated card {id} into {new_id}");
+        Ok(new_id)
+    }
 
-    use super::*;
+    /// Applies a batch of reviews, each given as the card, the grade and when the review took place.
+    ///
+    /// Reviews for cards that don't exist are rejected before anything is written.
+    pub async fn submit_reviews(&self, reviews: Vec<(CardId, Recall, Duration)>) -> Result<()> {
+        let mut by_card: BTreeMap<CardId, Vec<Review>> = BTreeMap::new();
 
-    pub async fn export(app: &App) -> String {
-        let mut dot = String::from("digraph G {\nranksep=2.0;\nrankdir=BT;\n");
-        let mut relations = BTreeSet::default();
-        let cards = app.load_all_cards().await;
+        for (id, grade, timestamp) in reviews {
+            by_card.entry(id).or_default().push(Review {
+                timestamp,
+                grade,
+                time_spent: Default::default(),
+            });
+        }
+
+        let mut cards = vec![];
+        for &id in by_card.keys() {
+            let Some(card) = self.card_provider.load(id).await else {
+                eyre::bail!("couldn't find card: {id}");
+            };
+            cards.push(card);
+        }
 
         for card in cards {
-            let label = card
-                .print()
-                .await
-                .to_string()
-                .replace(")", "")
-                .replace("(", "")
-                .replace("\"", "");
+            let mut history = card.history().clone();
+            history.insert_many(by_card.remove(&card.id()).unwrap_or_default());
+            self.card_provider.save_reviews(history).await;
 
-            let color = match card.recall_rate() {
-                _ if !card.is_finished() => yellow_color(),
-                Some(rate) => rate_to_color(rate as f64 * 100.),
-                None => cyan_color(),
-            };
+            let mut meta = card.meta();
+            if meta.scheduled.take().is_some() {
+                self.provider.metadata.save_item(meta).await;
+            }
+            self.card_provider.invalidate_card_and_deps(card.id()).await;
+        }
 
-            match card.recall_rate() {
-                Some(rate) => {
-                    let recall_rate = rate * 100.;
-                    let maturity = card.maybeturity().unwrap_or_default();
-                    dot.push_str(&format!(
-                        "    \"{}\" [label=\"{} ({:.0}%/{:.0}d)\", style=filled, fillcolor=\"{}\"];\n",
-                        card.id(),
-                        label,
-                        recall_rate,
-                        maturity,
-                        color
-                    ));
+        Ok(())
+    }
+
+    /// Creates a card asking the other way around, with the front and back of a normal card swapped.
+    ///
+    /// Only works for cards with a text backside. The new card is linked to the original through [`Card::reverse_of`].
+    pub async fn create_reverse(&self, id: CardId) -> Result<CardId> {
+        let Some(card) = self.card_provider.load(id).await else {
+            eyre::bail!("couldn't find card: {id}");
+        };
+
+        let CardType::Normal(NormalCard { front, back }) = card.card_type() else {
+            eyre::bail!(
+                "can only reverse normal cards, {id} is a {} card",
+                card.card_type().type_name()
+            );
+        };
+
+        let BackSide::Text(back) = back else {
+            eyre::bail!("can only reverse cards with a text backside");
+        };
+
+        let mut base = BaseCard::new(NormalCard {
+            front: back.clone(),
+            back: BackSide::Text(front.clone()),
+        });
+        base.reverse_of = Some(id);
+
+        let reverse = self.card_provider.save_basecard(base).await.id();
+        info!("created reverse {reverse} of card {id}");
+        Ok(reverse)
+    }
+
+    /// Merges the `remove` card into the `keep` card.
+    ///
+    /// Every reference to `remove` (dependencies, classes, attributes, backsides and collections)
+    /// is rewritten to point at `keep`, the review histories are merged, and `remove` is deleted.
+    /// A merge rejected by the checks writes nothing, but the writes themselves happen one record
+    /// at a time, so an interrupted merge can leave some references rewritten and others not.
+    ///
+    /// Like any deleted card, `remove` leaves its review history and metadata behind, these show up
+    /// in [`Self::integrity_report`].
+    pub async fn merge_cards(&self, keep: CardId, remove: CardId) -> Result<()> {
+        info!("merging card {remove} into {keep}");
+
+        if keep == remove {
+            eyre::bail!("can't merge card {keep} into itself");
+        }
+
+        let Some(keep_card) = self.card_provider.load(keep).await else {
+            eyre::bail!("couldn't find card: {keep}");
+        };
+
+        let Some(remove_card) = self.card_provider.load(remove).await else {
+            eyre::bail!("couldn't find card: {remove}");
+        };
+
+        if remove_card.all_dependencies().await.contains(&keep)
+            || remove_card.all_dependents().await.contains(&keep)
+        {
+            eyre::bail!("merging {remove} into {keep} would create a cycle");
+        }
+
+        // Everything is rewritten in memory first, nothing is written until all of it checks out.
+        let mut cards = vec![];
+        for card in self.load_all_cards().await {
+            if card.id() == remove {
+                continue;
+            }
+
+            let mut card = Arc::unwrap_or_clone(card);
+            let old_base = card.base.clone();
+
+            if card.base.dependencies.remove(&remove) && card.id() != keep {
+                card.base.dependencies.insert(keep);
+            }
+            card.base.ty.replace_ref(remove, keep);
+
+            if card.base.dependencies != old_base.dependencies || card.base.ty != old_base.ty {
+                if card.dependency_ids().await.contains(&card.id()) {
+                    eyre::bail!(
+                        "merging {remove} into {keep} would make card {} depend on itself",
+                        card.id()
+                    );
                 }
-                None => {
-                    dot.push_str(&format!(
-                        "    \"{}\" [label=\"{} \", style=filled, fillcolor=\"{}\"];\n",
-                        card.id(),
-                        label,
-                        color
-                    ));
+                cards.push(card);
+            }
+        }
+
+        let mut attrs = vec![];
+        for (_, mut attr) in self.provider.attrs.load_all().await {
+            if attr.class == remove || attr.back_type == Some(remove) {
+                if attr.class == remove {
+                    attr.class = keep;
+                }
+                if attr.back_type == Some(remove) {
+                    attr.back_type = Some(keep);
                 }
+                attrs.push(attr);
             }
+        }
 
-            // Create edges for dependencies, also enclosing IDs in quotes
-            for child_id in card.dependency_ids().await {
-                relations.insert(format!("    \"{}\" -> \"{}\";\n", card.id(), child_id));
+        let mut cols = vec![];
+        for (_, mut col) in self.provider.collections.load_all().await {
+            if col.dyncards.iter().any(|dy| dy.card_id() == Some(remove)) {
+                for dy in col.dyncards.iter_mut() {
+                    dy.replace_ref(remove, keep);
+                }
+                let mut seen: Vec<DynCard> = vec![];
+                col.dyncards.retain(|dy| {
+                    if seen.contains(dy) {
+                        false
+                    } else {
+                        seen.push(dy.clone());
+                        true
+                    }
+                });
+                cols.push(col);
             }
         }
 
-        for rel in relations {
-            dot.push_str(&rel);
+        // Every reference is swapped the same way, so cards that were valid before stay valid.
+        // Validating one card at a time here would check it against a half-merged state.
+        for mut card in cards {
+            card.save_unchecked().await;
         }
 
-        dot.push_str("}\n");
-        dot
+        for attr in attrs {
+            self.provider.attrs.save_item(attr).await;
+        }
+
+        for col in cols {
+            self.provider.collections.save(col).await;
+        }
+
+        let mut history = keep_card.history().clone();
+        history.insert_many(remove_card.history().inner().clone());
+        self.card_provider.save_reviews(history).await;
+
+        self.card_provider.remove_card(remove).await;
+        self.card_provider.invalidate_card_and_deps(keep).await;
+
+        info!("done merging card {remove} into {keep}");
+        Ok(())
+    }
+
+    /// Every collection's id and name, along with how many cards it expands to.
+    ///
+    /// Entries referencing cards that no longer exist don't count towards the total.
+    pub async fn list_collections(&self) -> Vec<(CollectionId, String, usize)> {
+        let mut out = vec![];
+        for col in self.provider.collections.load_all().await.into_values() {
+            let count = col
+                .expand(self.card_provider(), Default::default())
+                .await
+                .len();
+            out.push((col.id, col.name, count));
+        }
+
+        out.sort_by(|a, b| a.1.cmp(&b.1));
+        out
+    }
+
+    /// Checks each reason a card could be left out of a review session with the given filter.
+    pub async fn explain_reviewable(
+        &self,
+        id: CardId,
+        filter: &CardFilter,
+    ) -> Result<ReviewabilityExplanation> {
+        let Some(card) = self.card_provider.load(id).await else {
+            eyre::bail!("couldn't find card: {id}");
+        };
+
+        Ok(ReviewabilityExplanation {
+            finished: card.is_finished(),
+            trivial: card.is_trivial(),
+            has_back_side: card.has_back_side(),
+            suspended: card.is_suspended(),
+            min_rec_recall_rate: card.min_rec_recall_rate().await,
+            prereq_allowed: match &filter.prereq {
+                Some(policy) => Some(policy.allows(&card).await),
+                None => None,
+            },
+            passes_filter: filter.filter(card).await,
+        })
+    }
+
+    /// Finds references to cards that don't exist.
+    pub async fn integrity_report(&self) -> IntegrityReport {
+        // Deleted cards are still stored, so the ids come from the cards that actually load.
+        let cards: HashSet<CardId> = self
+            .load_all_cards()
+            .await
+            .iter()
+            .map(|card| card.id())
+            .collect();
+        let mut report = IntegrityReport::default();
+
+        for col in self.provider.collections.load_all().await.into_values() {
+            for dyncard in &col.dyncards {
+                if let Some(id) = dyncard.card_id() {
+                    if !cards.contains(&id) {
+                        report.collection_entries.push((col.id, id));
+                    }
+                }
+            }
+        }
+
+        for id in self.provider.reviews.load_ids_sorted().await {
+            if !cards.contains(&id) {
+                report.reviews.push(id);
+            }
+        }
+
+        for id in self.provider.metadata.load_ids_sorted().await {
+            if !cards.contains(&id) {
+                report.metadata.push(id);
+            }
+        }
+
+        report
+    }
+
+    /// Removes collection entries that reference cards that don't exist.
+    ///
+    /// Orphaned reviews and metadata are left alone since they can't be deleted.
+    pub async fn fix_integrity(&self) -> IntegrityReport {
+        let report = self.integrity_report().await;
+
+        for mut col in self.provider.collections.load_all().await.into_values() {
+            let before = col.dyncards.len();
+            col.dyncards.retain(|dyncard| {
+                dyncard
+                    .card_id()
+                    .is_none_or(|id| !report.collection_entries.contains(&(col.id, id)))
+            });
+
+            if col.dyncards.len() != before {
+                info!("removed dangling entries from collection {}", col.name);
+                self.provider.collections.save(col).await;
+            }
+        }
+
+        report
+    }
+
+    /// Removes the card from every collection it's in, this also happens when the card is deleted.
+    pub async fn remove_card_from_all_collections(&self, id: CardId) {
+        self.card_provider
+            .remove_card_from_all_collections(id)
+            .await;
+    }
+
+    /// Saves the review queue so the session can be resumed later with [`Self::load_session`].
+    pub async fn save_session(&self, queue: &[CardId], position: usize) {
+        let session = ReviewSession::new(queue.to_vec(), position);
+        self.provider.sessions.save_item(session).await;
+    }
+
+    /// Loads the saved review session, if any.
+    ///
+    /// Cards that have been deleted since are dropped from the queue, and if most of them are gone the session is discarded.
+    pub async fn load_session(&self) -> Option<ReviewSession> {
+        let mut session = self.provider.sessions.load_item(ReviewSession::ID).await?;
+        let cards: HashSet<CardId> = self
+            .load_all_cards()
+            .await
+            .iter()
+            .map(|card| card.id())
+            .collect();
+
+        let original_len = session.queue.len();
+        let mut position = session.position;
+        let mut queue = Vec::with_capacity(original_len);
+        for (idx, id) in session.queue.into_iter().enumerate() {
+            if cards.contains(&id) {
+                queue.push(id);
+            } else if idx < session.position {
+                position -= 1;
+            }
+        }
+
+        if queue.len() * 2 < original_len {
+            info!("discarding saved session, most of its cards are gone");
+            self.clear_session().await;
+            return None;
+        }
+
+        session.queue = queue;
+        session.position = position.min(session.queue.len());
+        Some(session)
+    }
+
+    pub async fn clear_session(&self) {
+        if let Some(session) = self.provider.sessions.load_item(ReviewSession::ID).await {
+            self.provider.sessions.delete_item(session).await;
+        }
+    }
+
+    /// The saved review settings, or the defaults if none were saved.
+    ///
+    /// Errors if the saved key bindings bind the same key more than once.
+    pub async fn load_review_settings(&self) -> Result<ReviewSettings> {
+        let settings = self
+            .provider
+            .settings
+            .load_item(ReviewSettings::ID)
+            .await
+            .unwrap_or_default();
+
+        settings.bindings.validate()?;
+        Ok(settings)
+    }
+
+    /// Saves the review settings, unless the key bindings bind the same key more than once.
+    pub async fn save_review_settings(&self, settings: ReviewSettings) -> Result<()> {
+        settings.bindings.validate()?;
+        self.provider.settings.save_item(settings).await;
+        Ok(())
     }
 
-    // Convert recall rate to a color, from red to green
-    fn rate_to_color(rate: f64) -> String {
-        let red = ((1.0 - rate / 100.0) * 255.0) as u8;
-        let green = (rate / 100.0 * 255.0) as u8;
-        format!("#{:02X}{:02X}00", red, green) // RGB color in hex
+    /// Moves the given cards from one collection to another.
+    ///
+    /// Cards that aren't in `from` are still added to `to`. Both collections are only saved once all changes are made.
+    pub async fn move_cards(
+        &self,
+        cards: &[CardId],
+        from: CollectionId,
+        to: CollectionId,
+    ) -> Result<()> {
+        if from == to {
+            return Ok(());
+        }
+
+        let Some(mut from_col) = self.provider.collections.load(from).await else {
+            eyre::bail!("couldn't find collection: {from}");
+        };
+        let Some(mut to_col) = self.provider.collections.load(to).await else {
+            eyre::bail!("couldn't find collection: {to}");
+        };
+
+        from_col
+            .dyncards
+            .retain(|dyncard| !matches!(dyncard, DynCard::Card(id) if cards.contains(id)));
+
+        for id in cards {
+            let dyncard = DynCard::Card(*id);
+            if !to_col.dyncards.contains(&dyncard) {
+                to_col.dyncards.push(dyncard);
+            }
+        }
+
+        self.provider.collections.save(from_col).await;
+        self.provider.collections.save(to_col).await;
+        Ok(())
     }
 
-    fn cyan_color() -> String {
-        String::from("#00FFFF")
+    /// Pairs of cards whose fronts are similar, along with their similarity from 0 to 1.
+    ///
+    /// Similarity is the jaccard index of the character bigrams of the lowercased fronts.
+    pub async fn fuzzy_duplicates(&self, threshold: f32) -> Vec<(CardId, CardId, f32)> {
+        let mut fronts = vec![];
+        for card in self.load_all_cards().await {
+            fronts.push((card.id(), bigrams(&card.print().await)));
+        }
+
+        let mut pairs = vec![];
+        for (idx, (left_id, left)) in fronts.iter().enumerate() {
+            for (right_id, right) in &fronts[idx + 1..] {
+                let similarity = jaccard(left, right);
+                if similarity >= threshold {
+                    pairs.push((*left_id, *right_id, similarity));
+                }
+            }
+        }
+
+        pairs.sort_by(|a, b| b.2.total_cmp(&a.2));
+        pairs
     }
 
-    fn yellow_color() -> String {
-        String::from("#FFFF00")
+    pub async fn load_class_cards(&self) -> Vec<Arc<Card>> {
+        self.load_all_cards()
+            .await
+            .into_iter()
+            .filter(|card| card.is_class())
+            .collect()
+    }
+}
+
+/// The UTC date of a unix timestamp.
+fn utc_date(timestamp: Duration) -> NaiveDate {
+    DateTime::from_timestamp(timestamp.as_secs() as i64, 0)
+        .unwrap_or_default()
+        .date_naive()
+}
+
+fn bigrams(s: &str) -> HashSet<(char, char)> {
+    let chars: Vec<char> = s
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect();
+    chars.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+fn jaccard(left: &HashSet<(char, char)>, right: &HashSet<(char, char)>) -> f32 {
+    let union = left.union(right).count();
+    if union == 0 {
+        return 0.0;
+    }
+    left.intersection(right).count() as f32 / union as f32
+}
+
+/// References to cards that don't exist, see [`App::integrity_report`].
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct IntegrityReport {
+    /// Collection entries based on a missing card.
+    pub collection_entries: Vec<(CollectionId, CardId)>,
+    /// Review histories of missing cards.
+    pub reviews: Vec<CardId>,
+    /// Metadata of missing cards.
+    pub metadata: Vec<CardId>,
+}
+
+impl IntegrityReport {
+    pub fn is_empty(&self) -> bool {
+        self.collection_entries.is_empty() && self.reviews.is_empty() && self.metadata.is_empty()
+    }
+}
+
+/// Result of [`App::explain_reviewable`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewabilityExplanation {
+    pub finished: bool,
+    pub trivial: bool,
+    pub has_back_side: bool,
+    pub suspended: bool,
+    /// Lowest recall rate among the card's recursive dependencies.
+    pub min_rec_recall_rate: RecallRate,
+    /// Whether the filter's prerequisite policy lets the card through, `None` if it has no such policy.
+    pub prereq_allowed: Option<bool>,
+    /// Whether the card passes the filter as a whole.
+    pub passes_filter: bool,
+}
+
+pub async fn as_graph(app: &App) -> String {
+    graphviz::export(app, GraphStyle::default()).await
+}
+
+pub async fn as_graph_with_style(app: &App, style: GraphStyle) -> String {
+    graphviz::export(app, style).await
+}
+
+/// Review histories with the card content stripped out, for sharing to improve the recall algorithms.
+///
+/// Each card is only identified by an HMAC-SHA256 of its id keyed with `salt`, which should be a secret
+/// kept per user. The same salt gives the same ids across exports, and without it they can't be mapped
+/// back to the cards. Timestamps are in seconds since the card's first review.
+pub async fn export_anonymized_reviews(app: &App, salt: &[u8]) -> String {
+    anonymized::export(app, salt).await
+}
+
+mod anonymized {
+    use hmac::{Hmac, Mac};
+    use serde::Serialize;
+    use sha2::Sha256;
+    use speki_dto::Item;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    struct CardReviews {
+        card: String,
+        reviews: Vec<AnonReview>,
+    }
+
+    #[derive(Serialize)]
+    struct AnonReview {
+        t: u64,
+        grade: Recall,
+    }
+
+    fn hash_id(id: CardId, salt: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(salt).expect("hmac takes keys of any length");
+        mac.update(id.as_bytes());
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    pub async fn export(app: &App, salt: &[u8]) -> String {
+        let mut out: Vec<CardReviews> = app
+            .provider
+            .reviews
+            .load_all()
+            .await
+            .into_values()
+            .filter_map(|history| {
+                let first = history.first()?.timestamp;
+                let reviews = history
+                    .inner()
+                    .iter()
+                    .map(|review| AnonReview {
+                        t: review.timestamp.saturating_sub(first).as_secs(),
+                        grade: review.grade,
+                    })
+                    .collect();
+
+                Some(CardReviews {
+                    card: hash_id(history.id(), salt),
+                    reviews,
+                })
+            })
+            .collect();
+
+        out.sort_by(|a, b| a.card.cmp(&b.card));
+        serde_json::to_string(&out).unwrap()
+    }
+}
+
+/// Exports the dependency graph as a JSON adjacency list.
+pub async fn as_graph_json(app: &App) -> String {
+    graphjson::export(app).await
+}
+
+mod graphjson {
+    use serde::Serialize;
+
+    use super::*;
+    use crate::card::CardRefType;
+
+    #[derive(Serialize)]
+    struct Graph {
+        nodes: Vec<Node>,
+        edges: Vec<Edge>,
+    }
+
+    #[derive(Serialize)]
+    struct Node {
+        id: CardId,
+        label: String,
+        #[serde(rename = "type")]
+        ty: String,
+        recall_rate: Option<RecallRate>,
+        maturity: Option<f32>,
+        /// Grows logarithmically with the maturity in days, unreviewed cards have size 1.
+        size: f32,
+    }
+
+    #[derive(Serialize)]
+    struct Edge {
+        from: CardId,
+        to: CardId,
+        ref_type: CardRefType,
+        style: &'static str,
+    }
+
+    fn node_size(maturity: Option<f32>) -> f32 {
+        1. + maturity.unwrap_or_default().max(0.).ln_1p()
+    }
+
+    /// Prerequisite chains are drawn solid, structural links between classes, instances and events dashed.
+    fn edge_style(ref_type: CardRefType) -> &'static str {
+        match ref_type {
+            CardRefType::ExplicitDependency => "solid",
+            CardRefType::ClassOfInstance
+            | CardRefType::ParentClass
+            | CardRefType::InstanceOfAttribute
+            | CardRefType::ParentEvent => "dashed",
+            CardRefType::BackRef => "dotted",
+        }
+    }
+
+    pub async fn export(app: &App) -> String {
+        let mut nodes = vec![];
+        let mut edges = vec![];
+
+        let mut cards = app.load_all_cards().await;
+        cards.sort_by_key(|card| card.id());
+
+        for card in cards {
+            let maturity = card.maybeturity();
+            nodes.push(Node {
+                id: card.id(),
+                label: card.print().await,
+                ty: card.card_type().type_name().to_string(),
+                recall_rate: card.recall_rate(),
+                maturity,
+                size: node_size(maturity),
+            });
+
+            for (ref_type, to) in card.dependency_refs() {
+                edges.push(Edge {
+                    from: card.id(),
+                    to,
+                    ref_type,
+                    style: edge_style(ref_type),
+                });
+            }
+        }
+
+        serde_json::to_string(&Graph { nodes, edges }).unwrap()
+    }
+}
+
+pub use graphviz::{ColorRamp, GraphStyle};
+
+mod graphviz {
+    use std::collections::BTreeSet;
+
+    use crate::card::CType;
+
+    use super::*;
+
+    /// How the nodes of the graphviz export are drawn.
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct GraphStyle {
+        pub ramp: ColorRamp,
+        /// Draw each card type with its own node shape.
+        pub shape_by_type: bool,
+        /// Add a subgraph explaining the colors.
+        pub legend: bool,
+    }
+
+    /// Colors used for the recall rate of a card.
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ColorRamp {
+        /// Red for forgotten, green for remembered.
+        #[default]
+        RedGreen,
+        /// Orange to blue, distinguishable with the common kinds of colorblindness.
+        OrangeBlue,
+        /// Dark to light gray, suited for dark backgrounds.
+        Grayscale,
+    }
+
+    impl ColorRamp {
+        /// Convert recall rate to a color, rate goes from 0 to 100.
+        fn rate_color(&self, rate: f64) -> String {
+            let high = (rate / 100.0).clamp(0.0, 1.0);
+            let low = 1.0 - high;
+            let (red, green, blue) = match self {
+                Self::RedGreen => (low * 255.0, high * 255.0, 0.0),
+                Self::OrangeBlue => (low * 230.0, 100.0 + low * 59.0, high * 230.0),
+                Self::Grayscale => (
+                    64.0 + high * 191.0,
+                    64.0 + high * 191.0,
+                    64.0 + high * 191.0,
+                ),
+            };
+            format!("#{:02X}{:02X}{:02X}", red as u8, green as u8, blue as u8)
+        }
+
+        fn unreviewed_color(&self) -> String {
+            match self {
+                Self::RedGreen => String::from("#00FFFF"),
+                Self::OrangeBlue => String::from("#CC79A7"),
+                Self::Grayscale => String::from("#FFFFFF"),
+            }
+        }
+
+        fn unfinished_color(&self) -> String {
+            match self {
+                Self::RedGreen => String::from("#FFFF00"),
+                Self::OrangeBlue => String::from("#F0E442"),
+                Self::Grayscale => String::from("#000000"),
+            }
+        }
+    }
+
+    fn shape(ty: CType) -> &'static str {
+        match ty {
+            CType::Class => "box",
+            CType::Instance => "ellipse",
+            CType::Attribute => "diamond",
+            CType::Statement => "note",
+            CType::Event => "hexagon",
+            CType::Unfinished => "octagon",
+            CType::Normal => "ellipse",
+        }
+    }
+
+    fn legend(style: &GraphStyle) -> String {
+        let entries = [
+            ("unfinished", style.ramp.unfinished_color()),
+            ("unreviewed", style.ramp.unreviewed_color()),
+            ("0%", style.ramp.rate_color(0.)),
+            ("50%", style.ramp.rate_color(50.)),
+            ("100%", style.ramp.rate_color(100.)),
+        ];
+
+        let mut dot = String::from("    subgraph cluster_legend {\n    label=\"legend\";\n");
+        for (label, color) in entries {
+            dot.push_str(&format!(
+                "    \"legend_{label}\" [label=\"{label}\", style=filled, fillcolor=\"{color}\"];\n"
+            ));
+        }
+        dot.push_str("    }\n");
+        dot
+    }
+
+    pub async fn export(app: &App, style: GraphStyle) -> String {
+        let mut dot = String::from("digraph G {\nranksep=2.0;\nrankdir=BT;\n");
+        let mut relations = BTreeSet::default();
+        let mut cards = app.load_all_cards().await;
+        // Sorted so the output is the same between runs and diffs cleanly.
+        cards.sort_by_key(|card| card.id());
+
+        for card in cards {
+            let label = card
+                .print()
+                .await
+                .to_string()
+                .replace(")", "")
+                .replace("(", "")
+                .replace("\"", "");
+
+            let color = match card.recall_rate() {
+                _ if !card.is_finished() => style.ramp.unfinished_color(),
+                Some(rate) => style.ramp.rate_color(rate as f64 * 100.),
+                None => style.ramp.unreviewed_color(),
+            };
+
+            let shape = if style.shape_by_type {
+                format!(", shape={}", shape(card.card_type().fieldless()))
+            } else {
+                String::new()
+            };
+
+            match card.recall_rate() {
+                Some(rate) => {
+                    let recall_rate = rate * 100.;
+                    let maturity = card.maybeturity().unwrap_or_default();
+                    dot.push_str(&format!(
+                        "    \"{}\" [label=\"{} ({:.0}%/{:.0}d)\", style=filled, fillcolor=\"{}\"{}];\n",
+                        card.id(),
+                        label,
+                        recall_rate,
+                        maturity,
+                        color,
+                        shape
+                    ));
+                }
+                None => {
+                    dot.push_str(&format!(
+                        "    \"{}\" [label=\"{} \", style=filled, fillcolor=\"{}\"{}];\n",
+                        card.id(),
+                        label,
+                        color,
+                        shape
+                    ));
+                }
+            }
+
+            // Create edges for dependencies, also enclosing IDs in quotes
+            for child_id in card.dependency_ids().await {
+                relations.insert(format!("    \"{}\" -> \"{}\";\n", card.id(), child_id));
+            }
+        }
+
+        for rel in relations {
+            dot.push_str(&rel);
+        }
+
+        if style.legend {
+            dot.push_str(&legend(&style));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+    use speki_provider::MemProvider;
+
+    use super::*;
+    use crate::testing::{test_app, ControlledTime};
+
+    #[test]
+    fn test_mem_round_trip() {
+        block_on(async {
+            let time = ControlledTime::default();
+            let storage = MemProvider::new_with_time(time.clone());
+            let app = App::new(SimpleRecall, time.clone(), Provider::new(storage.clone()));
+
+            let id = app.add_card("front".to_string(), "back".to_string()).await;
+            app.submit_reviews(vec![(id, Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+
+            // A second app on the same storage sees everything the first one saved.
+            let app = App::new(SimpleRecall, time.clone(), Provider::new(storage));
+            let card = app.load_card(id).await.unwrap();
+            assert_eq!(card.print().await, "front");
+            assert_eq!(card.display_backside().await.as_deref(), Some("back"));
+            assert_eq!(card.history().inner().len(), 1);
+            assert_eq!(app.load_cards().await, vec![id]);
+        });
+    }
+
+    #[test]
+    fn test_mem_filtered_load() {
+        block_on(async {
+            let (app, _) = test_app();
+            let finished = app.add_card("a".to_string(), "b".to_string()).await;
+            app.add_unfinished("c".to_string()).await;
+
+            let filter = CardFilter {
+                finished: Some(true),
+                ..Default::default()
+            };
+            let ids: Vec<CardId> = app
+                .cards_filtered(filter)
+                .await
+                .iter()
+                .map(|card| card.id())
+                .collect();
+            assert_eq!(ids, vec![finished]);
+        });
+    }
+
+    #[test]
+    fn test_new_cards_per_day_cap() {
+        block_on(async {
+            let (app, time) = test_app();
+            let mut ids = vec![];
+            for i in 0..5 {
+                ids.push(app.add_card(format!("front{i}"), "back".to_string()).await);
+            }
+
+            let filter = CardFilter {
+                new_cards_per_day: Some(2),
+                ..Default::default()
+            };
+            assert_eq!(app.cards_filtered(filter.clone()).await.len(), 2);
+
+            // A card introduced today uses up one of the two slots, but stays in the result since it's no longer new.
+            app.submit_reviews(vec![(ids[0], Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+            let cards = app.cards_filtered(filter.clone()).await;
+            assert_eq!(cards.len(), 2);
+            assert_eq!(cards.iter().filter(|card| card.is_pending()).count(), 1);
+
+            let all = app.load_all_cards().await;
+            assert_eq!(app.filter_cards(all, &filter).await.len(), 2);
+        });
+    }
+
+    /// Adds an instance with one attribute card per answer, returning the attribute cards.
+    async fn add_attribute_cards(app: &App, answers: &[&str]) -> Vec<CardId> {
+        let class = app
+            .add_class("person".to_string(), "human".to_string(), None)
+            .await;
+        let instance = app
+            .add_instance("alice".to_string(), None::<String>, class)
+            .await;
+
+        let mut ids = vec![];
+        for answer in answers {
+            let attribute = AttributeDTO {
+                pattern: format!("{answer} of {{}}?"),
+                id: AttributeId::new_v4(),
+                class,
+                back_type: None,
+                list_valued: false,
+                last_modified: Default::default(),
+                deleted: false,
+                source: Default::default(),
+            };
+            let data = AttributeCard {
+                attribute: attribute.id,
+                back: answer.to_string().into(),
+                instance,
+            };
+            app.provider.attrs.save_item(attribute).await;
+            let card = app.card_provider.save_basecard(BaseCard::new(data)).await;
+            ids.push(card.id());
+        }
+
+        ids
+    }
+
+    #[test]
+    fn test_bury_siblings() {
+        block_on(async {
+            let (app, time) = test_app();
+            let attrs = add_attribute_cards(&app, &["age", "height", "birthplace"]).await;
+
+            let filter = CardFilter {
+                bury_siblings: true,
+                ..Default::default()
+            };
+            let siblings_in_queue = |cards: Vec<Arc<Card>>| {
+                cards
+                    .iter()
+                    .filter(|card| attrs.contains(&card.id()))
+                    .map(|card| card.id())
+                    .collect::<Vec<_>>()
+            };
+
+            let queued = siblings_in_queue(app.cards_filtered(filter.clone()).await);
+            assert_eq!(queued.len(), 1);
+
+            // After reviewing one of them the others stay buried, even though the reviewed card is still due.
+            app.submit_reviews(vec![(attrs[1], Recall::None, time.current_time())])
+                .await
+                .unwrap();
+            let queued = siblings_in_queue(app.cards_filtered(filter.clone()).await);
+            assert_eq!(queued, vec![attrs[1]]);
+
+            let filter = CardFilter {
+                bury_siblings: false,
+                ..filter
+            };
+            assert_eq!(siblings_in_queue(app.cards_filtered(filter).await).len(), 3);
+        });
+    }
+
+    async fn set_priority(app: &App, id: CardId, priority: i32) {
+        app.load_card(id)
+            .await
+            .unwrap()
+            .set_priority(priority)
+            .await;
+    }
+
+    fn ids(cards: &[Arc<Card>]) -> Vec<CardId> {
+        cards.iter().map(|card| card.id()).collect()
+    }
+
+    #[test]
+    fn test_order_by_priority() {
+        block_on(async {
+            let (app, time) = test_app();
+            let mut all = vec![];
+            for i in 0..4 {
+                all.push(app.add_card(format!("front{i}"), "back".to_string()).await);
+            }
+            let [seen_low, new_low, seen_high, new_high] = all[..] else {
+                unreachable!()
+            };
+            app.submit_reviews(vec![
+                (seen_low, Recall::Perfect, time.current_time()),
+                (seen_high, Recall::Perfect, time.current_time()),
+            ])
+            .await
+            .unwrap();
+
+            let cards = app.card_provider.load_many(all.clone()).await;
+            let filter = CardFilter::default();
+
+            // Without priorities the order is left alone.
+            assert_eq!(ids(&app.filter_cards(cards.clone(), &filter).await), all);
+
+            set_priority(&app, seen_high, 5).await;
+            set_priority(&app, new_high, 1).await;
+            let cards = app.card_provider.load_many(all.clone()).await;
+
+            // Each group keeps its slots, higher priority goes first within it.
+            assert_eq!(
+                ids(&app.filter_cards(cards, &filter).await),
+                vec![seen_high, new_high, seen_low, new_low]
+            );
+        });
+    }
+
+    #[test]
+    fn test_priority_does_not_make_cards_due() {
+        block_on(async {
+            let (app, time) = test_app();
+            let id = app.add_card("front".to_string(), "back".to_string()).await;
+            app.submit_reviews(vec![(id, Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+            set_priority(&app, id, 100).await;
+
+            let filter = CardFilter {
+                recall: Some(cardfilter::NumOp {
+                    num: 0.8,
+                    ord: cardfilter::MyNumOrd::Less,
+                }),
+                ..Default::default()
+            };
+            assert!(app.cards_filtered(filter).await.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_review_paths_agree() {
+        block_on(async {
+            let (app, time) = test_app();
+            let now = time.current_time();
+            let reviewed = app.add_card("a".to_string(), "b".to_string()).await;
+            let submitted = app.add_card("c".to_string(), "d".to_string()).await;
+
+            let mut card = app.load_card(reviewed).await.unwrap();
+            card.add_review(Recall::Perfect).await;
+            card.add_review(Recall::Perfect).await;
+
+            app.submit_reviews(vec![
+                (submitted, Recall::Perfect, now),
+                (submitted, Recall::Perfect, now),
+            ])
+            .await
+            .unwrap();
+
+            for id in [reviewed, submitted] {
+                let card = app.load_card(id).await.unwrap();
+                let reviews = card.history().inner();
+                assert_eq!(reviews.len(), 2);
+                assert!(reviews.iter().all(|review| review.timestamp == now));
+            }
+        });
+    }
+
+    #[test]
+    fn test_merge_cards() {
+        block_on(async {
+            let (app, time) = test_app();
+            let keep = app
+                .add_class("person".to_string(), "".to_string(), None)
+                .await;
+            let remove = app
+                .add_class("human".to_string(), "".to_string(), None)
+                .await;
+            let instance = app
+                .add_instance("alice".to_string(), None::<String>, remove)
+                .await;
+            let attribute = AttributeDTO {
+                pattern: "age of {}?".to_string(),
+                id: AttributeId::new_v4(),
+                class: remove,
+                back_type: None,
+                list_valued: false,
+                last_modified: Default::default(),
+                deleted: false,
+                source: Default::default(),
+            };
+            app.provider.attrs.save_item(attribute.clone()).await;
+            let data = AttributeCard {
+                attribute: attribute.id,
+                back: "30".to_string().into(),
+                instance,
+            };
+            let attr_card = app
+                .card_provider
+                .save_basecard(BaseCard::new(data))
+                .await
+                .id();
+
+            let dependent = app.add_card("a".to_string(), "b".to_string()).await;
+            let mut card = app.load_card(dependent).await.unwrap();
+            card.add_dependency(remove).await.unwrap();
+            let pointing = app
+                .add_card("kind of alice?".to_string(), BackSide::Card(remove))
+                .await;
+
+            let mut col = Collection::new("people".to_string());
+            col.dyncards = vec![
+                DynCard::Card(keep),
+                DynCard::Card(instance),
+                DynCard::Card(remove),
+            ];
+            app.provider.collections.save(col.clone()).await;
+
+            app.submit_reviews(vec![
+                (keep, Recall::Perfect, time.current_time()),
+                (remove, Recall::None, time.current_time()),
+            ])
+            .await
+            .unwrap();
+
+            app.merge_cards(keep, remove).await.unwrap();
+
+            let col = app.provider.collections.load(col.id).await.unwrap();
+            assert_eq!(
+                col.dyncards,
+                vec![DynCard::Card(keep), DynCard::Card(instance)]
+            );
+
+            assert!(app.load_card(remove).await.is_none());
+
+            let card = app.load_card(dependent).await.unwrap();
+            let deps = card.dependency_ids().await;
+            assert!(deps.contains(&keep));
+            assert!(!deps.contains(&remove));
+
+            let card = app.load_card(pointing).await.unwrap();
+            assert_eq!(card.back_side(), Some(&BackSide::Card(keep)));
+
+            let card = app.load_card(instance).await.unwrap();
+            assert_eq!(card.load_ancestor_classes().await.unwrap(), vec![keep]);
+            let attribute = app.provider.attrs.load_item(attribute.id).await.unwrap();
+            assert_eq!(attribute.class, keep);
+            app.load_card(attr_card)
+                .await
+                .unwrap()
+                .validate()
+                .await
+                .unwrap();
+
+            let card = app.load_card(keep).await.unwrap();
+            let grades: Vec<Recall> = card.history().inner().iter().map(|r| r.grade).collect();
+            assert_eq!(grades, vec![Recall::Perfect, Recall::None]);
+        });
+    }
+
+    #[test]
+    fn test_duplicate_card() {
+        block_on(async {
+            let (app, time) = test_app();
+            let dep = app.add_card("a".to_string(), "b".to_string()).await;
+            let mut base = BaseCard::new(NormalCard {
+                front: "front".to_string(),
+                back: "back".to_string().into(),
+            });
+            base.dependencies.insert(dep);
+            base.front_audio = Some(audio::AudioId::new_v4());
+            let original = app.card_provider.save_basecard(base).await.id();
+            app.submit_reviews(vec![(original, Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+
+            let copy = app.duplicate_card(original).await.unwrap();
+            assert_ne!(copy, original);
+
+            let original = app.load_card(original).await.unwrap();
+            let copy = app.load_card(copy).await.unwrap();
+            assert_eq!(copy.base.ty, original.base.ty);
+            assert_eq!(copy.dependency_ids().await, BTreeSet::from([dep]));
+            assert_eq!(copy.base.front_audio, original.base.front_audio);
+            assert!(copy.history().inner().is_empty());
+            assert_eq!(original.history().inner().len(), 1);
+
+            assert!(app.duplicate_card(CardId::new_v4()).await.is_err());
+        });
+    }
+
+    #[test]
+    fn test_anonymized_export() {
+        block_on(async {
+            let (app, time) = test_app();
+            let first = app.add_card("a".to_string(), "b".to_string()).await;
+            let second = app.add_card("c".to_string(), "d".to_string()).await;
+            let start = time.current_time();
+            app.submit_reviews(vec![
+                (first, Recall::None, start),
+                (first, Recall::Perfect, start + Duration::from_secs(60)),
+                (second, Recall::Some, start),
+            ])
+            .await
+            .unwrap();
+
+            let export = export_anonymized_reviews(&app, b"secret").await;
+            let parsed: serde_json::Value = serde_json::from_str(&export).unwrap();
+            let entries = parsed.as_array().unwrap();
+            assert_eq!(entries.len(), 2);
+
+            let hashes: Vec<&str> = entries
+                .iter()
+                .map(|entry| entry["card"].as_str().unwrap())
+                .collect();
+            assert_ne!(hashes[0], hashes[1]);
+            for id in [first, second] {
+                for hash in &hashes {
+                    assert!(!hash.contains(&id.simple().to_string()));
+                    assert!(!hash.contains(&id.to_string()));
+                }
+            }
+            assert!(!export.contains("\"a\""));
+
+            let timestamps: Vec<Vec<u64>> = entries
+                .iter()
+                .map(|entry| {
+                    entry["reviews"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|review| review["t"].as_u64().unwrap())
+                        .collect()
+                })
+                .collect();
+            assert!(timestamps.contains(&vec![0, 60]));
+            assert!(timestamps.contains(&vec![0]));
+
+            assert_eq!(export_anonymized_reviews(&app, b"secret").await, export);
+            assert_ne!(export_anonymized_reviews(&app, b"other").await, export);
+        });
+    }
+
+    #[test]
+    fn test_review_activity() {
+        block_on(async {
+            let (app, time) = test_app();
+            let now = time.current_time();
+            let hour = Duration::from_secs(3600);
+            let id = app.add_card("a".to_string(), "b".to_string()).await;
+            app.submit_reviews(vec![
+                (id, Recall::Perfect, now),
+                (id, Recall::Perfect, now - hour * 2),
+                (id, Recall::Perfect, now - hour * 23),
+                (id, Recall::Perfect, now - hour * 72),
+            ])
+            .await
+            .unwrap();
+
+            // The controlled clock starts at 2023-11-14 22:13:20 UTC.
+            let day = |d| NaiveDate::from_ymd_opt(2023, 11, d).unwrap();
+            assert_eq!(
+                app.review_activity(3).await,
+                BTreeMap::from([(day(12), 0), (day(13), 1), (day(14), 2)])
+            );
+            assert!(app.review_activity(0).await.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_graph_json() {
+        block_on(async {
+            let (app, _) = test_app();
+            let class = app
+                .add_class("person".to_string(), "".to_string(), None)
+                .await;
+            let instance = app
+                .add_instance("alice".to_string(), None::<String>, class)
+                .await;
+            let card = app
+                .add_card("best friend?".to_string(), BackSide::Card(instance))
+                .await;
+            app.load_card(card)
+                .await
+                .unwrap()
+                .add_dependency(class)
+                .await
+                .unwrap();
+
+            let graph: serde_json::Value =
+                serde_json::from_str(&as_graph_json(&app).await).unwrap();
+
+            let nodes = graph["nodes"].as_array().unwrap();
+            assert_eq!(nodes.len(), 3);
+            let node = nodes
+                .iter()
+                .find(|node| node["id"] == instance.to_string())
+                .unwrap();
+            assert_eq!(node["label"], "alice");
+            assert_eq!(node["type"], "instance");
+
+            let mut edges: Vec<(String, String, String)> = graph["edges"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|edge| {
+                    (
+                        edge["from"].as_str().unwrap().to_string(),
+                        edge["to"].as_str().unwrap().to_string(),
+                        edge["ref_type"].as_str().unwrap().to_string(),
+                    )
+                })
+                .collect();
+            edges.sort();
+
+            let mut expected = vec![
+                (instance, class, "class_of_instance"),
+                (card, instance, "back_ref"),
+                (card, class, "explicit_dependency"),
+            ]
+            .into_iter()
+            .map(|(from, to, ty)| (from.to_string(), to.to_string(), ty.to_string()))
+            .collect::<Vec<_>>();
+            expected.sort();
+            assert_eq!(edges, expected);
+        });
+    }
+
+    #[test]
+    fn test_graph_style() {
+        block_on(async {
+            let (app, time) = test_app();
+            let class = app
+                .add_class("person".to_string(), "".to_string(), None)
+                .await;
+            let card = app.add_card("a".to_string(), "b".to_string()).await;
+            app.submit_reviews(vec![(card, Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+            let class_node = |dot: &str| {
+                dot.lines()
+                    .find(|line| line.contains(&format!("\"{class}\" [")))
+                    .unwrap()
+                    .to_string()
+            };
+
+            let dot = as_graph(&app).await;
+            assert!(class_node(&dot).contains("fillcolor=\"#00FFFF\"]"));
+            assert!(!dot.contains("shape="));
+            assert!(!dot.contains("cluster_legend"));
+
+            let style = GraphStyle {
+                ramp: ColorRamp::OrangeBlue,
+                shape_by_type: true,
+                legend: true,
+            };
+            let dot = as_graph_with_style(&app, style).await;
+            assert!(class_node(&dot).contains("fillcolor=\"#CC79A7\", shape=box]"));
+            assert!(dot.contains("cluster_legend"));
+            assert!(
+                dot.contains("\"legend_0%\" [label=\"0%\", style=filled, fillcolor=\"#E69F00\"]")
+            );
+            assert!(dot
+                .contains("\"legend_100%\" [label=\"100%\", style=filled, fillcolor=\"#0064E6\"]"));
+            assert!(dot.trim_end().ends_with('}'));
+        });
+    }
+
+    #[test]
+    fn test_fuzzy_duplicates() {
+        block_on(async {
+            let (app, _) = test_app();
+            let france = app
+                .add_card(
+                    "What is the capital of France?".to_string(),
+                    "paris".to_string(),
+                )
+                .await;
+            let same = app
+                .add_card(
+                    "what is the capital of france".to_string(),
+                    "paris".to_string(),
+                )
+                .await;
+            let germany = app
+                .add_card(
+                    "What is the capital of Germany?".to_string(),
+                    "berlin".to_string(),
+                )
+                .await;
+            app.add_card("photosynthesis".to_string(), "light".to_string())
+                .await;
+
+            let pairs = app.fuzzy_duplicates(0.6).await;
+            let pair_ids: Vec<BTreeSet<CardId>> = pairs
+                .iter()
+                .map(|(left, right, _)| BTreeSet::from([*left, *right]))
+                .collect();
+            assert_eq!(pair_ids.len(), 3);
+            // Case and punctuation are ignored, and the most similar pair comes first.
+            assert_eq!(pair_ids[0], BTreeSet::from([france, same]));
+            assert_eq!(pairs[0].2, 1.0);
+            assert!(pair_ids.contains(&BTreeSet::from([france, germany])));
+            assert!(pair_ids.contains(&BTreeSet::from([same, germany])));
+            assert!(pairs[1].2 < 1.0);
+
+            assert_eq!(app.fuzzy_duplicates(1.0).await.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_list_collections() {
+        block_on(async {
+            let (app, _) = test_app();
+            let dep = app.add_card("a".to_string(), "b".to_string()).await;
+            let card = app.add_card("c".to_string(), "d".to_string()).await;
+            app.load_card(card)
+                .await
+                .unwrap()
+                .add_dependency(dep)
+                .await
+                .unwrap();
+            let missing = CardId::new_v4();
+
+            let mut words = Collection::new("words".to_string());
+            words.dyncards = vec![
+                DynCard::Card(card),
+                DynCard::Card(missing),
+                DynCard::Instances(missing),
+                DynCard::Dependents(missing),
+                DynCard::RecDependents(missing),
+            ];
+            let empty = Collection::new("empty".to_string());
+            app.provider.collections.save(words.clone()).await;
+            app.provider.collections.save(empty.clone()).await;
+
+            // Dependencies are counted, entries pointing to missing cards are not.
+            assert_eq!(
+                app.list_collections().await,
+                vec![
+                    (empty.id, "empty".to_string(), 0),
+                    (words.id, "words".to_string(), 2)
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn test_move_cards() {
+        block_on(async {
+            let (app, _) = test_app();
+            let moved = app.add_card("a".to_string(), "b".to_string()).await;
+            let stays = app.add_card("c".to_string(), "d".to_string()).await;
+            let outside = app.add_card("e".to_string(), "f".to_string()).await;
+
+            let mut from = Collection::new("from".to_string());
+            from.dyncards = vec![
+                DynCard::Card(moved),
+                DynCard::Card(stays),
+                DynCard::Dependents(moved),
+            ];
+            let mut to = Collection::new("to".to_string());
+            to.dyncards = vec![DynCard::Card(moved)];
+            app.provider.collections.save(from.clone()).await;
+            app.provider.collections.save(to.clone()).await;
+
+            app.move_cards(&[moved, outside], from.id, to.id)
+                .await
+                .unwrap();
+
+            let from_col = app.provider.collections.load(from.id).await.unwrap();
+            assert_eq!(
+                from_col.dyncards,
+                vec![DynCard::Card(stays), DynCard::Dependents(moved)]
+            );
+            let to_col = app.provider.collections.load(to.id).await.unwrap();
+            assert_eq!(
+                to_col.dyncards,
+                vec![DynCard::Card(moved), DynCard::Card(outside)]
+            );
+
+            // Moving into the same collection is a no-op, and unknown collections are an error.
+            app.move_cards(&[stays], from.id, from.id).await.unwrap();
+            assert_eq!(
+                app.provider.collections.load(from.id).await.unwrap(),
+                from_col
+            );
+            assert!(app
+                .move_cards(&[stays], from.id, CollectionId::new_v4())
+                .await
+                .is_err());
+            assert_eq!(
+                app.provider.collections.load(from.id).await.unwrap(),
+                from_col
+            );
+        });
+    }
+
+    #[test]
+    fn test_explain_reviewable() {
+        block_on(async {
+            let (app, _) = test_app();
+            let dep = app.add_card("a".to_string(), "b".to_string()).await;
+            let id = app.add_card("c".to_string(), "d".to_string()).await;
+            let mut card = app.load_card(id).await.unwrap();
+            card.add_dependency(dep).await.unwrap();
+            card.set_suspend(true).await;
+
+            let filter = CardFilter {
+                suspended: Some(false),
+                prereq: Some(cardfilter::PrereqPolicy::Strict),
+                ..Default::default()
+            };
+            assert_eq!(
+                app.explain_reviewable(id, &filter).await.unwrap(),
+                ReviewabilityExplanation {
+                    finished: true,
+                    trivial: false,
+                    has_back_side: true,
+                    suspended: true,
+                    min_rec_recall_rate: 0.0,
+                    prereq_allowed: Some(false),
+                    passes_filter: false,
+                }
+            );
+
+            let unfinished = app.add_unfinished("e".to_string()).await;
+            let explanation = app
+                .explain_reviewable(unfinished, &CardFilter::default())
+                .await
+                .unwrap();
+            assert!(!explanation.finished);
+            assert!(!explanation.has_back_side);
+            assert_eq!(explanation.prereq_allowed, None);
+
+            assert!(app
+                .explain_reviewable(CardId::new_v4(), &filter)
+                .await
+                .is_err());
+        });
+    }
+
+    #[test]
+    fn test_integrity() {
+        block_on(async {
+            let (app, time) = test_app();
+            let card = app.add_card("a".to_string(), "b".to_string()).await;
+            let missing = CardId::new_v4();
+            app.provider.reviews.save_item(History::new(missing)).await;
+            app.provider
+                .metadata
+                .save_item(Metadata::new(missing))
+                .await;
+
+            let mut col = Collection::new("words".to_string());
+            col.dyncards = vec![
+                DynCard::Card(card),
+                DynCard::Card(missing),
+                DynCard::Dependents(missing),
+            ];
+            app.provider.collections.save(col.clone()).await;
+            assert!(!app.integrity_report().await.is_empty());
+
+            let expected = IntegrityReport {
+                collection_entries: vec![(col.id, missing), (col.id, missing)],
+                reviews: vec![missing],
+                metadata: vec![missing],
+            };
+            assert_eq!(app.integrity_report().await, expected);
+            assert_eq!(app.fix_integrity().await, expected);
+
+            let col = app.provider.collections.load(col.id).await.unwrap();
+            assert_eq!(col.dyncards, vec![DynCard::Card(card)]);
+            assert_eq!(
+                app.integrity_report().await,
+                IntegrityReport {
+                    collection_entries: vec![],
+                    ..expected
+                }
+            );
+
+            // Deleted cards are still stored but count as missing.
+            let deleted = app.add_card("c".to_string(), "d".to_string()).await;
+            app.submit_reviews(vec![(deleted, Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+            app.load_card(deleted).await.unwrap().delete_card().await;
+            assert!(app.integrity_report().await.reviews.contains(&deleted));
+        });
+    }
+
+    #[test]
+    fn test_count() {
+        block_on(async {
+            let (app, time) = test_app();
+            assert_eq!(app.provider.cards.count().await, 0);
+
+            let first = app.add_card("a".to_string(), "b".to_string()).await;
+            let second = app.add_card("c".to_string(), "d".to_string()).await;
+            app.submit_reviews(vec![(first, Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+            assert_eq!(app.provider.cards.count().await, 2);
+            assert_eq!(app.provider.reviews.count().await, 1);
+
+            // Deleted cards are still stored, so they're still counted.
+            app.load_card(second).await.unwrap().delete_card().await;
+            assert_eq!(app.provider.cards.count().await, 2);
+        });
+    }
+
+    #[test]
+    fn test_resume_session() {
+        block_on(async {
+            let (app, _) = test_app();
+            let mut queue = vec![];
+            for i in 0..4 {
+                queue.push(app.add_card(i.to_string(), "back".to_string()).await);
+            }
+            app.save_session(&queue, 2).await;
+
+            // A deleted card before the position moves it back by one.
+            app.load_card(queue[0]).await.unwrap().delete_card().await;
+            let session = app.load_session().await.unwrap();
+            assert_eq!(session.queue, queue[1..]);
+            assert_eq!(session.position, 1);
+
+            // Once most of the cards are gone the session is dropped.
+            for id in &queue[1..3] {
+                app.load_card(*id).await.unwrap().delete_card().await;
+            }
+            assert!(app.load_session().await.is_none());
+            assert!(app
+                .provider
+                .sessions
+                .load_item(ReviewSession::ID)
+                .await
+                .is_none());
+        });
+    }
+
+    #[test]
+    fn test_graph_json_style() {
+        block_on(async {
+            let (app, time) = test_app();
+            let class = app
+                .add_class("person".to_string(), "".to_string(), None)
+                .await;
+            let instance = app
+                .add_instance("alice".to_string(), None::<String>, class)
+                .await;
+            let card = app
+                .add_card("best friend?".to_string(), BackSide::Card(instance))
+                .await;
+            app.load_card(card)
+                .await
+                .unwrap()
+                .add_dependency(class)
+                .await
+                .unwrap();
+            app.submit_reviews(vec![(card, Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+
+            let graph: serde_json::Value =
+                serde_json::from_str(&as_graph_json(&app).await).unwrap();
+            let size = |id: CardId| {
+                graph["nodes"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .find(|node| node["id"] == id.to_string())
+                    .unwrap()["size"]
+                    .as_f64()
+                    .unwrap()
+            };
+            assert_eq!(size(class), 1.0);
+            assert!(size(card) > 1.0);
+
+            let style = |ref_type: &str| {
+                graph["edges"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .find(|edge| edge["ref_type"] == ref_type)
+                    .unwrap()["style"]
+                    .clone()
+            };
+            assert_eq!(style("explicit_dependency"), "solid");
+            assert_eq!(style("class_of_instance"), "dashed");
+            assert_eq!(style("back_ref"), "dotted");
+        });
+    }
+
+    #[test]
+    fn test_submit_reviews() {
+        block_on(async {
+            let (app, time) = test_app();
+            let now = time.current_time();
+            let hour = Duration::from_secs(3600);
+            let first = app.add_card("a".to_string(), "b".to_string()).await;
+            let second = app.add_card("c".to_string(), "d".to_string()).await;
+
+            app.submit_reviews(vec![
+                (first, Recall::Late, now + hour),
+                (second, Recall::Perfect, now),
+                (first, Recall::None, now),
+            ])
+            .await
+            .unwrap();
+
+            let card = app.load_card(first).await.unwrap();
+            let reviews: Vec<(Duration, Recall)> = card
+                .history()
+                .inner()
+                .iter()
+                .map(|review| (review.timestamp, review.grade))
+                .collect();
+            assert_eq!(
+                reviews,
+                vec![(now, Recall::None), (now + hour, Recall::Late)]
+            );
+            assert_eq!(
+                app.load_card(second).await.unwrap().history().inner().len(),
+                1
+            );
+
+            // Nothing is written if any of the cards are missing.
+            let result = app
+                .submit_reviews(vec![
+                    (second, Recall::Perfect, now + hour),
+                    (CardId::new_v4(), Recall::Perfect, now + hour),
+                ])
+                .await;
+            assert!(result.is_err());
+            assert_eq!(
+                app.load_card(second).await.unwrap().history().inner().len(),
+                1
+            );
+        });
+    }
+
+    #[test]
+    fn test_quick_add() {
+        block_on(async {
+            let (app, _) = test_app();
+            let ty = |id: CardId| {
+                let app = &app;
+                async move { app.load_card(id).await.unwrap().card_type().fieldless() }
+            };
+
+            let id = app
+                .quick_add("a".to_string(), Some("b".to_string()), CType::Statement)
+                .await
+                .unwrap();
+            assert_eq!(ty(id).await, CType::Normal);
+
+            let id = app
+                .quick_add("c".to_string(), None, CType::Statement)
+                .await
+                .unwrap();
+            assert_eq!(ty(id).await, CType::Statement);
+
+            let id = app
+                .quick_add("d".to_string(), None, CType::Unfinished)
+                .await
+                .unwrap();
+            assert_eq!(ty(id).await, CType::Unfinished);
+            assert_eq!(app.load_card(id).await.unwrap().print().await, "d");
+
+            assert!(app
+                .quick_add("e".to_string(), None, CType::Normal)
+                .await
+                .is_err());
+            assert_eq!(app.load_cards().await.len(), 3);
+        });
+    }
+
+    #[test]
+    fn test_create_reverse() {
+        block_on(async {
+            let (app, _) = test_app();
+            let id = app
+                .add_card("capital of france?".to_string(), "paris".to_string())
+                .await;
+
+            let reverse = app.create_reverse(id).await.unwrap();
+            let card = app.load_card(reverse).await.unwrap();
+            assert_eq!(card.print().await, "paris");
+            assert_eq!(
+                card.display_backside().await.as_deref(),
+                Some("capital of france?")
+            );
+            assert_eq!(card.reverse_of(), Some(id));
+            assert_eq!(app.load_card(id).await.unwrap().reverse_of(), None);
+
+            let paris = app.add_card("paris".to_string(), "".to_string()).await;
+            let reference = app
+                .add_card("capital of france?".to_string(), BackSide::Card(paris))
+                .await;
+            assert!(app.create_reverse(reference).await.is_err());
+            let unfinished = app.add_unfinished("a".to_string()).await;
+            assert!(app.create_reverse(unfinished).await.is_err());
+            assert!(app.create_reverse(CardId::new_v4()).await.is_err());
+        });
+    }
+
+    #[test]
+    fn test_graph_exports_are_stable() {
+        block_on(async {
+            let time = ControlledTime::default();
+            let storage = MemProvider::new_with_time(time.clone());
+            let app = App::new(SimpleRecall, time.clone(), Provider::new(storage.clone()));
+            let mut ids = vec![];
+            for i in 0..8 {
+                ids.push(app.add_card(i.to_string(), "back".to_string()).await);
+            }
+            ids.sort();
+
+            let graph: serde_json::Value =
+                serde_json::from_str(&as_graph_json(&app).await).unwrap();
+            let node_ids: Vec<String> = graph["nodes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|node| node["id"].as_str().unwrap().to_string())
+                .collect();
+            let expected: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+            assert_eq!(node_ids, expected);
+
+            // Another app on the same storage fills its cache in a different order.
+            let other = App::new(SimpleRecall, time, Provider::new(storage));
+            other.load_card(ids[5]).await.unwrap();
+            assert_eq!(as_graph(&app).await, as_graph(&other).await);
+            assert_eq!(as_graph_json(&app).await, as_graph_json(&other).await);
+        });
+    }
+
+    #[test]
+    fn test_forecast_workload() {
+        block_on(async {
+            let (app, time) = test_app();
+            let day = Duration::from_secs(86400);
+            app.add_card("new".to_string(), "b".to_string()).await;
+            let old = app.add_card("old".to_string(), "d".to_string()).await;
+            app.submit_reviews(vec![(old, Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+            time.inc(day * 3);
+            let recent = app.add_card("recent".to_string(), "f".to_string()).await;
+            app.submit_reviews(vec![(recent, Recall::Perfect, time.current_time())])
+                .await
+                .unwrap();
+
+            let filter = CardFilter {
+                recall: Some(cardfilter::NumOp {
+                    num: 0.8,
+                    ord: cardfilter::MyNumOrd::Less,
+                }),
+                ..Default::default()
+            };
+            let days = 30;
+
+            // The first day each reviewed card drops below the threshold.
+            let first_due = |id: CardId| {
+                let app = &app;
+                async move {
+                    let card = app.load_card(id).await.unwrap();
+                    (0..days)
+                        .find(|d| card.recall_rate_ahead(day * *d).unwrap() < 0.8)
+                        .unwrap() as usize
+                }
+            };
+            let (old_due, recent_due) = (first_due(old).await, first_due(recent).await);
+            assert!(old_due < recent_due);
+
+            let mut expected = vec![0; days as usize];
+            expected[0] += 1;
+            expected[old_due] += 1;
+            expected[recent_due] += 1;
+            assert_eq!(app.forecast_workload(filter.clone(), days).await, expected);
+
+            assert!(app.forecast_workload(filter, 0).await.is_empty());
+        });
     }
 }
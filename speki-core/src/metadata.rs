@@ -12,6 +12,24 @@ use crate::card::CardId;
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Metadata {
     pub suspended: IsSuspended,
+    /// Card was flagged as needing to be cleaned up.
+    #[serde(default)]
+    pub needs_work: bool,
+    /// Card is too obvious to be worth reviewing.
+    #[serde(default)]
+    pub trivial: bool,
+    /// Unix time when the card should be reviewed next, overriding the recall algorithm.
+    #[serde(default)]
+    pub scheduled: Option<Duration>,
+    /// Difficulty as rated by the user, independent of the recall algorithm.
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,
+    /// Cards with a higher priority are reviewed first, it doesn't affect when a card is due.
+    #[serde(default)]
+    pub priority: i32,
+    /// Unix time until which the card is hidden from reviews, separate from it being suspended.
+    #[serde(default)]
+    pub snooze_until: Option<Duration>,
     last_modified: Duration,
     id: Uuid,
     source: ModifiedSource,
@@ -22,6 +40,12 @@ impl Metadata {
         Self {
             id,
             suspended: Default::default(),
+            needs_work: false,
+            trivial: false,
+            scheduled: None,
+            difficulty: None,
+            priority: 0,
+            snooze_until: None,
             last_modified: Default::default(),
             source: Default::default(),
         }
@@ -62,6 +86,14 @@ impl Item for Metadata {
     }
 }
 
+#[derive(Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
 #[derive(Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Clone)]
 pub enum IsSuspended {
     False,
@@ -98,6 +130,11 @@ impl IsSuspended {
     pub fn is_suspended(&self) -> bool {
         !matches!(self, IsSuspended::False)
     }
+
+    /// Like [`Self::is_suspended`] but a temporary suspension no longer counts once its time has passed.
+    pub fn is_suspended_at(&self, current_time: Duration) -> bool {
+        self.clone().verify_time(current_time).is_suspended()
+    }
 }
 
 impl Serialize for IsSuspended {
@@ -104,6 +104,17 @@ impl History {
         self.reviews.last().cloned()
     }
 
+    pub fn first(&self) -> Option<Review> {
+        self.reviews.first().cloned()
+    }
+
+    /// A copy of the history with only the first `k` reviews.
+    pub fn truncated(&self, k: usize) -> Self {
+        let mut history = self.clone();
+        history.reviews.truncate(k);
+        history
+    }
+
     pub fn lapses_since(&self, dur: Duration, current_time: Duration) -> u32 {
         let since = current_time - dur;
         self.reviews
@@ -131,7 +142,7 @@ impl History {
 
     pub fn time_since_last_review(&self, current_unix: Duration) -> Option<Duration> {
         let last = self.reviews.last()?;
-        Some(current_unix - last.timestamp)
+        Some(current_unix.saturating_sub(last.timestamp))
     }
 
     pub fn len(&self) -> usize {
@@ -142,6 +153,35 @@ impl History {
         self.len() == 0
     }
 
+    /// How much the review history can be trusted to say how well the card is known.
+    ///
+    /// Based on the standard error of the success rate, which shrinks with more reviews and grows with mixed results.
+    pub fn confidence(&self) -> Confidence {
+        let n = self.len();
+        if n == 0 {
+            return Confidence::None;
+        }
+
+        let successes = self
+            .reviews
+            .iter()
+            .filter(|review| matches!(review.grade, Recall::Some | Recall::Perfect))
+            .count();
+
+        let p = successes as f64 / n as f64;
+        // All-pass or all-fail histories have no variance, but a couple of them still says little.
+        let variance = (p * (1. - p)).max(0.25 / n as f64);
+        let std_err = (variance / n as f64).sqrt();
+
+        if std_err < 0.1 {
+            Confidence::High
+        } else if std_err < 0.2 {
+            Confidence::Medium
+        } else {
+            Confidence::Low
+        }
+    }
+
     pub fn new(id: CardId) -> Self {
         Self {
             id,
@@ -150,21 +190,42 @@ impl History {
         }
     }
 
+    /// Inserts a review in timestamp order, after any reviews with the same timestamp.
+    ///
+    /// Reviews are kept as they are, even if an identical one is already there.
     pub fn push(&mut self, review: Review) {
-        self.reviews.push(review);
+        let idx = self
+            .reviews
+            .partition_point(|r| r.timestamp <= review.timestamp);
+        self.reviews.insert(idx, review);
     }
 
     pub fn insert_many(&mut self, reviews: impl IntoIterator<Item = Review>) {
-        self.reviews.extend(reviews);
-        self.reviews.sort_by_key(|r| r.timestamp);
-        self.reviews.dedup();
+        for review in reviews {
+            self.push(review);
+        }
     }
 
+    /// Combines two copies of the same history, as when syncing.
+    ///
+    /// Reviews present in both copies are only kept once.
     pub fn merge_into(&mut self, other: Self) {
-        self.insert_many(other.reviews);
+        self.reviews.extend(other.reviews);
+        self.reviews.sort_by_key(|r| r.timestamp);
+        self.reviews.dedup();
     }
 }
 
+/// How reliable a card's recall estimate is, see [`History::confidence`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Confidence {
+    /// Never reviewed.
+    None,
+    Low,
+    Medium,
+    High,
+}
+
 #[derive(Ord, PartialOrd, Eq, PartialEq, Hash, Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Review {
     // When (unix time) did the review take place?
@@ -293,6 +354,46 @@ impl std::str::FromStr for Recall {
     }
 }
 
+/// Tally of the grades given during a single review session.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionSummary {
+    pub reviewed: usize,
+    pub again: usize,
+    pub hard: usize,
+    pub good: usize,
+    pub easy: usize,
+    pub time_spent: Duration,
+}
+
+impl SessionSummary {
+    pub fn record(&mut self, recall: Recall, time_spent: Duration) {
+        self.reviewed += 1;
+        self.time_spent += time_spent;
+
+        match recall {
+            Recall::None => self.again += 1,
+            Recall::Late => self.hard += 1,
+            Recall::Some => self.good += 1,
+            Recall::Perfect => self.easy += 1,
+        }
+    }
+}
+
+impl std::fmt::Display for SessionSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "reviewed {} cards in {}s (again: {}, hard: {}, good: {}, easy: {})",
+            self.reviewed,
+            self.time_spent.as_secs(),
+            self.again,
+            self.hard,
+            self.good,
+            self.easy
+        )
+    }
+}
+
 fn legacy_parse_history(s: String) -> Vec<Review> {
     let mut reviews = vec![];
     for line in s.lines() {
@@ -310,3 +411,254 @@ fn legacy_parse_history(s: String) -> Vec<Review> {
     reviews.sort_by_key(|r| r.timestamp);
     reviews
 }
+
+/// Log loss of a recaller's predictions over the reviews whose elapsed time fell in one bucket.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BucketReport {
+    /// Elapsed times from this one (inclusive) up to the next bucket's start fall in this bucket.
+    pub start: Duration,
+    pub count: usize,
+    pub log_loss: f64,
+}
+
+impl BucketReport {
+    /// Average log loss per review, `None` if the bucket is empty.
+    pub fn mean_log_loss(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.log_loss / self.count as f64)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecallerReport {
+    pub buckets: Vec<BucketReport>,
+}
+
+impl RecallerReport {
+    /// Day, week, month, year and anything longer.
+    pub fn default_boundaries() -> Vec<Duration> {
+        [0, 1, 7, 30, 365]
+            .into_iter()
+            .map(|days| Duration::from_secs(days * 86400))
+            .collect()
+    }
+
+    /// Index of the bucket an elapsed time falls into, given sorted bucket starts.
+    pub fn bucket_index(boundaries: &[Duration], elapsed: Duration) -> usize {
+        boundaries
+            .iter()
+            .rposition(|start| elapsed >= *start)
+            .unwrap_or_default()
+    }
+}
+
+/// Scores how well a recaller predicts each review from the reviews before it.
+///
+/// Every review after the first is predicted at its own timestamp from the preceding reviews,
+/// and the log loss of that prediction is added to the bucket matching the time elapsed since the previous review.
+/// `boundaries` are the sorted starts of each bucket.
+pub fn evaluate_recaller(
+    histories: &[History],
+    recaller: &(impl RecallCalc + ?Sized),
+    boundaries: &[Duration],
+) -> RecallerReport {
+    let mut buckets: Vec<BucketReport> = boundaries
+        .iter()
+        .map(|&start| BucketReport {
+            start,
+            count: 0,
+            log_loss: 0.,
+        })
+        .collect();
+
+    if buckets.is_empty() {
+        return RecallerReport { buckets };
+    }
+
+    for history in histories {
+        for (idx, pair) in history.reviews.windows(2).enumerate() {
+            let (prev, review) = (&pair[0], &pair[1]);
+            let Some(predicted) =
+                recaller.recall_rate(&history.truncated(idx + 1), review.timestamp)
+            else {
+                continue;
+            };
+
+            let predicted = (predicted as f64).clamp(1e-6, 1. - 1e-6);
+            let recalled = matches!(review.grade, Recall::Some | Recall::Perfect);
+            let loss = if recalled {
+                -predicted.ln()
+            } else {
+                -(1. - predicted).ln()
+            };
+
+            let elapsed = review.timestamp.saturating_sub(prev.timestamp);
+            let bucket = &mut buckets[RecallerReport::bucket_index(boundaries, elapsed)];
+            bucket.count += 1;
+            bucket.log_loss += loss;
+        }
+    }
+
+    RecallerReport { buckets }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_session_summary() {
+        let mut summary = SessionSummary::default();
+        summary.record(Recall::None, Duration::from_secs(10));
+        summary.record(Recall::Some, Duration::from_secs(20));
+        summary.record(Recall::Some, Duration::from_secs(5));
+        summary.record(Recall::Perfect, Duration::from_secs(1));
+
+        assert_eq!(
+            summary,
+            SessionSummary {
+                reviewed: 4,
+                again: 1,
+                hard: 0,
+                good: 2,
+                easy: 1,
+                time_spent: Duration::from_secs(36),
+            }
+        );
+        assert_eq!(
+            summary.to_string(),
+            "reviewed 4 cards in 36s (again: 1, hard: 0, good: 2, easy: 1)"
+        );
+    }
+
+    fn review(secs: u64, grade: Recall) -> Review {
+        Review {
+            timestamp: Duration::from_secs(secs),
+            grade,
+            time_spent: Duration::default(),
+        }
+    }
+
+    #[test]
+    fn test_push_keeps_timestamps() {
+        let mut history = History::new(Uuid::new_v4());
+        history.push(review(10, Recall::Perfect));
+        history.push(review(30, Recall::Some));
+        history.push(review(20, Recall::None));
+        history.push(review(30, Recall::Some));
+
+        let timestamps: Vec<u64> = history
+            .inner()
+            .iter()
+            .map(|r| r.timestamp.as_secs())
+            .collect();
+        assert_eq!(timestamps, vec![10, 20, 30, 30]);
+        assert_eq!(history.inner()[1].grade, Recall::None);
+        assert!(history
+            .inner()
+            .iter()
+            .all(|r| r.timestamp.subsec_nanos() == 0));
+    }
+
+    #[test]
+    fn test_insert_many_matches_push() {
+        let reviews = vec![
+            review(30, Recall::Some),
+            review(10, Recall::Perfect),
+            review(30, Recall::Some),
+        ];
+
+        let mut pushed = History::new(Uuid::nil());
+        for review in reviews.clone() {
+            pushed.push(review);
+        }
+
+        let mut inserted = History::new(Uuid::nil());
+        inserted.insert_many(reviews);
+
+        assert_eq!(pushed, inserted);
+        assert_eq!(inserted.len(), 3);
+    }
+
+    /// Always predicts a coin flip, and checks it's only given the reviews before the predicted one.
+    struct CoinFlip;
+
+    impl RecallCalc for CoinFlip {
+        fn recall_rate(&self, reviews: &History, current_unix: Duration) -> Option<RecallRate> {
+            assert!(reviews.reviews.iter().all(|r| r.timestamp < current_unix));
+            Some(0.5)
+        }
+    }
+
+    #[test]
+    fn test_evaluate_recaller() {
+        let day = Duration::from_secs(86400);
+        let mut history = History::new(CardId::new_v4());
+        for (timestamp, grade) in [
+            (day * 100, Recall::Perfect),
+            (day * 100 + Duration::from_secs(7200), Recall::Perfect),
+            (day * 103, Recall::None),
+            (day * 113, Recall::Some),
+        ] {
+            history.push(Review {
+                timestamp,
+                grade,
+                time_spent: Default::default(),
+            });
+        }
+
+        let boundaries = RecallerReport::default_boundaries();
+        let report = evaluate_recaller(&[history], &CoinFlip, &boundaries);
+        let counts: Vec<usize> = report.buckets.iter().map(|b| b.count).collect();
+        assert_eq!(counts, vec![1, 1, 1, 0, 0]);
+        for bucket in &report.buckets[..3] {
+            let loss = bucket.mean_log_loss().unwrap();
+            assert!((loss - 2f64.ln()).abs() < 1e-9);
+        }
+        assert_eq!(report.buckets[3].mean_log_loss(), None);
+
+        assert_eq!(RecallerReport::bucket_index(&boundaries, day), 1);
+        assert_eq!(RecallerReport::bucket_index(&boundaries, day * 400), 4);
+        assert!(evaluate_recaller(&[], &CoinFlip, &[]).buckets.is_empty());
+    }
+
+    fn history_with(grades: &[Recall]) -> History {
+        let mut history = History::new(CardId::new_v4());
+        for (idx, grade) in grades.iter().enumerate() {
+            history.push(Review {
+                timestamp: Duration::from_secs(idx as u64 * 86400),
+                grade: *grade,
+                time_spent: Default::default(),
+            });
+        }
+        history
+    }
+
+    #[test]
+    fn test_confidence() {
+        let passes = |n: usize| history_with(&vec![Recall::Perfect; n]).confidence();
+        let mixed = |n: usize| {
+            let grades: Vec<Recall> = (0..n)
+                .map(|i| {
+                    if i % 2 == 0 {
+                        Recall::Some
+                    } else {
+                        Recall::None
+                    }
+                })
+                .collect();
+            history_with(&grades).confidence()
+        };
+
+        assert_eq!(passes(0), Confidence::None);
+        // A few passes aren't taken as certain.
+        assert_eq!(passes(2), Confidence::Low);
+        assert_eq!(passes(4), Confidence::Medium);
+        assert_eq!(passes(8), Confidence::High);
+
+        assert_eq!(mixed(4), Confidence::Low);
+        assert_eq!(mixed(8), Confidence::Medium);
+        assert_eq!(mixed(30), Confidence::High);
+    }
+}
@@ -0,0 +1,321 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use speki_dto::{Item, ModifiedSource};
+use uuid::Uuid;
+
+use crate::{card::CardId, recall_rate::Recall};
+
+/// A review session in progress, saved so it can be resumed after a restart.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ReviewSession {
+    id: Uuid,
+    pub queue: Vec<CardId>,
+    /// Index in the queue of the next card to review.
+    pub position: usize,
+    last_modified: Duration,
+    deleted: bool,
+    source: ModifiedSource,
+}
+
+impl ReviewSession {
+    /// There's only ever one saved session, so it's always stored under the same id.
+    pub const ID: Uuid = Uuid::nil();
+
+    pub fn new(queue: Vec<CardId>, position: usize) -> Self {
+        Self {
+            id: Self::ID,
+            queue,
+            position,
+            last_modified: Default::default(),
+            deleted: false,
+            source: Default::default(),
+        }
+    }
+}
+
+impl Item for ReviewSession {
+    fn deleted(&self) -> bool {
+        self.deleted
+    }
+
+    fn set_delete(&mut self) {
+        self.deleted = true;
+    }
+
+    fn set_last_modified(&mut self, time: Duration) {
+        self.last_modified = time;
+    }
+
+    fn last_modified(&self) -> Duration {
+        self.last_modified
+    }
+
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn identifier() -> &'static str {
+        "review_session"
+    }
+
+    fn source(&self) -> ModifiedSource {
+        self.source
+    }
+
+    fn set_source(&mut self, source: ModifiedSource) {
+        self.source = source;
+    }
+}
+
+/// How many grades the user picks from when reviewing.
+///
+/// Reviews are always stored with the canonical four grades, this only affects the input.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GradingScheme {
+    /// Pass or fail, mapped to [`Recall::Some`] and [`Recall::None`].
+    TwoButton,
+    #[default]
+    FourButton,
+}
+
+impl GradingScheme {
+    /// The grades to offer, in the order of their keys.
+    pub fn grades(&self) -> &'static [Recall] {
+        match self {
+            Self::TwoButton => &[Recall::None, Recall::Some],
+            Self::FourButton => &[Recall::None, Recall::Late, Recall::Some, Recall::Perfect],
+        }
+    }
+}
+
+/// What a key press does during a review.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewAction {
+    Reveal,
+    Suspend,
+    Grade(Recall),
+}
+
+/// Keys used during a review session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBindings {
+    pub reveal: String,
+    pub suspend: String,
+    /// Keys for each grade of the [`GradingScheme`], in the same order as [`GradingScheme::grades`].
+    pub grades: Vec<String>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            reveal: " ".to_string(),
+            suspend: "s".to_string(),
+            grades: ["1", "2", "3", "4"].map(String::from).to_vec(),
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Errors if the same key is bound to more than one action.
+    pub fn validate(&self) -> eyre::Result<()> {
+        let mut seen = std::collections::HashSet::new();
+
+        for key in [&self.reveal, &self.suspend]
+            .into_iter()
+            .chain(&self.grades)
+        {
+            if !seen.insert(key) {
+                eyre::bail!("key {key:?} is bound more than once");
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn action(&self, key: &str, scheme: GradingScheme) -> Option<ReviewAction> {
+        if key == self.reveal {
+            return Some(ReviewAction::Reveal);
+        }
+
+        if key == self.suspend {
+            return Some(ReviewAction::Suspend);
+        }
+
+        let idx = self.grades.iter().position(|grade| grade == key)?;
+        scheme.grades().get(idx).copied().map(ReviewAction::Grade)
+    }
+}
+
+/// Preferences for how reviews are done, saved so they're the same on every start.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ReviewSettings {
+    id: Uuid,
+    #[serde(default)]
+    pub scheme: GradingScheme,
+    #[serde(default)]
+    pub bindings: KeyBindings,
+    last_modified: Duration,
+    deleted: bool,
+    source: ModifiedSource,
+}
+
+impl ReviewSettings {
+    /// There's only one set of settings, so it's always stored under the same id.
+    pub const ID: Uuid = Uuid::nil();
+}
+
+impl Default for ReviewSettings {
+    fn default() -> Self {
+        Self {
+            id: Self::ID,
+            scheme: Default::default(),
+            bindings: Default::default(),
+            last_modified: Default::default(),
+            deleted: false,
+            source: Default::default(),
+        }
+    }
+}
+
+impl Item for ReviewSettings {
+    fn deleted(&self) -> bool {
+        self.deleted
+    }
+
+    fn set_delete(&mut self) {
+        self.deleted = true;
+    }
+
+    fn set_last_modified(&mut self, time: Duration) {
+        self.last_modified = time;
+    }
+
+    fn last_modified(&self) -> Duration {
+        self.last_modified
+    }
+
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn identifier() -> &'static str {
+        "review_settings"
+    }
+
+    fn source(&self) -> ModifiedSource {
+        self.source
+    }
+
+    fn set_source(&mut self, source: ModifiedSource) {
+        self.source = source;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::testing::test_app;
+
+    #[test]
+    fn test_two_button_maps_to_canonical_grades() {
+        let [fail, pass] = GradingScheme::TwoButton.grades() else {
+            panic!("two-button grading should offer two grades");
+        };
+        assert_eq!(*fail, Recall::None);
+        assert_eq!(*pass, Recall::Some);
+
+        assert_eq!(
+            GradingScheme::FourButton.grades(),
+            &[Recall::None, Recall::Late, Recall::Some, Recall::Perfect]
+        );
+    }
+
+    #[test]
+    fn test_review_settings_are_saved() {
+        block_on(async {
+            let (app, _) = test_app();
+            assert_eq!(
+                app.load_review_settings().await.unwrap().scheme,
+                GradingScheme::FourButton
+            );
+
+            let mut settings = app.load_review_settings().await.unwrap();
+            settings.scheme = GradingScheme::TwoButton;
+            app.save_review_settings(settings).await.unwrap();
+
+            assert_eq!(
+                app.load_review_settings().await.unwrap().scheme,
+                GradingScheme::TwoButton
+            );
+        });
+    }
+
+    fn vim_bindings() -> KeyBindings {
+        KeyBindings {
+            reveal: "j".to_string(),
+            suspend: "x".to_string(),
+            grades: ["a", "s", "d", "f"].map(String::from).to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_key_binding_conflicts() {
+        assert!(KeyBindings::default().validate().is_ok());
+        assert!(vim_bindings().validate().is_ok());
+
+        let mut bindings = vim_bindings();
+        bindings.suspend = "j".to_string();
+        assert!(bindings.validate().is_err());
+
+        let mut bindings = vim_bindings();
+        bindings.grades[3] = "a".to_string();
+        assert!(bindings.validate().is_err());
+    }
+
+    #[test]
+    fn test_key_binding_routing() {
+        let bindings = vim_bindings();
+        let four = GradingScheme::FourButton;
+        let two = GradingScheme::TwoButton;
+
+        assert_eq!(bindings.action("j", four), Some(ReviewAction::Reveal));
+        assert_eq!(bindings.action("x", four), Some(ReviewAction::Suspend));
+        assert_eq!(
+            bindings.action("s", four),
+            Some(ReviewAction::Grade(Recall::Late))
+        );
+        assert_eq!(
+            bindings.action("f", four),
+            Some(ReviewAction::Grade(Recall::Perfect))
+        );
+        assert_eq!(bindings.action("1", four), None);
+
+        assert_eq!(
+            bindings.action("a", two),
+            Some(ReviewAction::Grade(Recall::None))
+        );
+        assert_eq!(
+            bindings.action("s", two),
+            Some(ReviewAction::Grade(Recall::Some))
+        );
+        assert_eq!(bindings.action("d", two), None);
+    }
+
+    #[test]
+    fn test_conflicting_bindings_are_rejected() {
+        block_on(async {
+            let (app, _) = test_app();
+            let mut settings = app.load_review_settings().await.unwrap();
+            settings.bindings.reveal = "s".to_string();
+            assert!(app.save_review_settings(settings.clone()).await.is_err());
+
+            // Settings that bypassed the check are still caught when loaded.
+            app.provider.settings.save_item(settings).await;
+            assert!(app.load_review_settings().await.is_err());
+        });
+    }
+}
@@ -0,0 +1,46 @@
+//! Helpers for running the app on top of in-memory storage in tests.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use speki_dto::TimeProvider;
+use speki_provider::MemProvider;
+
+use crate::{App, Provider, SimpleRecall};
+
+/// Clock that only moves when told to, clones share the same time.
+#[derive(Clone)]
+pub struct ControlledTime {
+    time: Arc<Mutex<Duration>>,
+}
+
+impl Default for ControlledTime {
+    /// Starts at a fixed point in time, so tests that depend on the day don't start at the unix epoch.
+    fn default() -> Self {
+        Self {
+            time: Arc::new(Mutex::new(Duration::from_secs(1_700_000_000))),
+        }
+    }
+}
+
+impl ControlledTime {
+    pub fn inc(&self, inc: Duration) {
+        *self.time.lock().unwrap() += inc;
+    }
+}
+
+impl TimeProvider for ControlledTime {
+    fn current_time(&self) -> Duration {
+        *self.time.lock().unwrap()
+    }
+}
+
+/// An app backed by a fresh [`MemProvider`], along with the clock it runs on.
+pub fn test_app() -> (App, ControlledTime) {
+    let time = ControlledTime::default();
+    let provider = MemProvider::new_with_time(time.clone());
+    let app = App::new(SimpleRecall, time.clone(), Provider::new(provider));
+    (app, time)
+}
@@ -327,6 +327,21 @@ pub trait SpekiProvider<T: Item>: Sync {
         self.load_all_records().await.into_keys().collect()
     }
 
+    /// Amount of stored records, including deleted ones.
+    ///
+    /// This is only as cheap as [`Self::load_ids`], which loads every record unless the provider
+    /// overrides it with a key-only listing.
+    async fn count(&self) -> usize {
+        self.load_ids().await.len()
+    }
+
+    /// Like [`Self::load_ids`] but in a stable order, for when the caller lists them.
+    async fn load_ids_sorted(&self) -> Vec<Uuid> {
+        let mut ids = self.load_ids().await;
+        ids.sort();
+        ids
+    }
+
     async fn load_item(&self, id: Uuid) -> Option<T> {
         let record = self.load_record(id).await?;
         match toml::from_str::<T>(&record.content) {
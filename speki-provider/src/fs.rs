@@ -93,6 +93,15 @@ impl<T: Item> SpekiProvider<T> for FileProvider {
         out
     }
 
+    async fn load_ids(&self) -> Vec<Uuid> {
+        let path = self.item_path(T::identifier());
+        load_dir_paths(&path)
+            .unwrap()
+            .into_iter()
+            .map(|file| file.file_name().unwrap().to_str().unwrap().parse().unwrap())
+            .collect()
+    }
+
     async fn save_record(&self, record: Record) {
         let id = record.id;
         let content = record.content;
@@ -15,3 +15,9 @@ mod dexie;
 
 #[cfg(feature = "dexie")]
 pub use dexie::{DexieProvider, WasmTime};
+
+#[cfg(feature = "mem")]
+mod mem;
+
+#[cfg(feature = "mem")]
+pub use mem::{MemProvider, SystemTimeProvider};
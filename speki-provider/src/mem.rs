@@ -0,0 +1,122 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use speki_dto::{Item, ProviderId, Record, SpekiProvider, Syncable, TimeProvider};
+use uuid::Uuid;
+
+#[derive(Copy, Clone)]
+pub struct SystemTimeProvider;
+
+impl TimeProvider for SystemTimeProvider {
+    fn current_time(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+    }
+}
+
+/// Provider that keeps everything in memory, mainly useful for tests.
+///
+/// Clones share the same storage, so the same instance can be handed to the app for every item type.
+#[derive(Clone)]
+pub struct MemProvider {
+    inner: Arc<Mutex<Inner>>,
+    time: Arc<dyn TimeProvider + Send + Sync>,
+}
+
+#[derive(Default)]
+struct Inner {
+    id: Option<ProviderId>,
+    records: HashMap<&'static str, HashMap<Uuid, Record>>,
+    sync_times: HashMap<(ProviderId, &'static str), Duration>,
+}
+
+impl Default for MemProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemProvider {
+    pub fn new() -> Self {
+        Self::new_with_time(SystemTimeProvider)
+    }
+
+    pub fn new_with_time(time: impl TimeProvider + Send + Sync + 'static) -> Self {
+        Self {
+            inner: Default::default(),
+            time: Arc::new(time),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl<T: Item> Syncable<T> for MemProvider {
+    async fn save_id(&self, id: ProviderId) {
+        self.inner.lock().unwrap().id = Some(id);
+    }
+
+    async fn load_id_opt(&self) -> Option<ProviderId> {
+        self.inner.lock().unwrap().id
+    }
+
+    async fn update_sync_info(&self, other: ProviderId, now: Duration) {
+        self.inner
+            .lock()
+            .unwrap()
+            .sync_times
+            .insert((other, T::identifier()), now);
+    }
+
+    async fn last_sync(&self, other: ProviderId) -> Duration {
+        self.inner
+            .lock()
+            .unwrap()
+            .sync_times
+            .get(&(other, T::identifier()))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait(?Send)]
+impl<T: Item> SpekiProvider<T> for MemProvider {
+    async fn load_record(&self, id: Uuid) -> Option<Record> {
+        self.inner
+            .lock()
+            .unwrap()
+            .records
+            .get(T::identifier())?
+            .get(&id)
+            .cloned()
+    }
+
+    async fn load_all_records(&self) -> HashMap<Uuid, Record> {
+        self.inner
+            .lock()
+            .unwrap()
+            .records
+            .get(T::identifier())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    async fn save_record(&self, record: Record) {
+        let id: Uuid = record.id.parse().unwrap();
+        self.inner
+            .lock()
+            .unwrap()
+            .records
+            .entry(T::identifier())
+            .or_default()
+            .insert(id, record);
+    }
+
+    async fn current_time(&self) -> Duration {
+        self.time.current_time()
+    }
+}
@@ -1,8 +1,8 @@
-use std::{fmt::Display, sync::Arc};
+use std::{fmt::Display, str::FromStr, sync::Arc, time::Duration};
 
 use dioxus::prelude::*;
 use serde::{Deserialize, Serialize};
-use speki_core::cardfilter::{CardFilter, MyNumOrd, NumOp, NumOrd};
+use speki_core::cardfilter::{CardFilter, MyNumOrd, NumOp, NumOrd, PrereqPolicy};
 use strum::EnumIter;
 use tracing::info;
 
@@ -21,6 +21,11 @@ pub struct FilterEditor {
     pub finished: BoolEntry,
     pub suspended: BoolEntry,
     pub pending: BoolEntry,
+    pub trivial: BoolEntry,
+    pub ahead: NumEntry,
+    pub new_cards_per_day: NumEntry,
+    pub prereq: PrereqEntry,
+    pub bury_siblings: Signal<bool>,
 }
 
 impl FilterEditor {
@@ -52,6 +57,11 @@ fn default_filter() -> CardFilter {
         suspended: Some(false),
         pending: None,
         lapses: None,
+        prereq: None,
+        trivial: Some(false),
+        ahead: None,
+        new_cards_per_day: None,
+        bury_siblings: false,
     }
 }
 
@@ -103,6 +113,89 @@ impl BoolEntry {
     }
 }
 
+/// A plain number without a comparison, empty means unset.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NumEntry {
+    input: Signal<String>,
+    name: Arc<String>,
+}
+
+impl NumEntry {
+    fn new(name: &str, val: Option<impl ToString>) -> Self {
+        Self {
+            input: Signal::new_in_scope(
+                val.map(|val| val.to_string()).unwrap_or_default(),
+                ScopeId::APP,
+            ),
+            name: Arc::new(name.to_string()),
+        }
+    }
+
+    pub fn get_value<T: FromStr>(&self) -> Option<T> {
+        self.input.cloned().parse().ok()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, EnumIter, PartialEq)]
+enum PrereqOpt {
+    Any,
+    Strict,
+    Lenient,
+    Threshold,
+}
+
+impl Display for PrereqOpt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            PrereqOpt::Any => "any",
+            PrereqOpt::Strict => "strict",
+            PrereqOpt::Lenient => "lenient",
+            PrereqOpt::Threshold => "threshold",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrereqEntry {
+    opt: DropDownMenu<PrereqOpt>,
+    threshold: NumEntry,
+}
+
+impl PrereqEntry {
+    fn from_policy(policy: Option<PrereqPolicy>) -> Self {
+        let (opt, threshold) = match policy {
+            None => (PrereqOpt::Any, None),
+            Some(PrereqPolicy::Strict) => (PrereqOpt::Strict, None),
+            Some(PrereqPolicy::Lenient) => (PrereqOpt::Lenient, None),
+            Some(PrereqPolicy::Threshold(threshold)) => (PrereqOpt::Threshold, Some(threshold)),
+        };
+
+        Self {
+            opt: DropDownMenu::new(
+                vec![
+                    PrereqOpt::Any,
+                    PrereqOpt::Strict,
+                    PrereqOpt::Lenient,
+                    PrereqOpt::Threshold,
+                ],
+                Some(opt),
+            ),
+            threshold: NumEntry::new("min recall", threshold),
+        }
+    }
+
+    pub fn get_value(&self) -> Option<PrereqPolicy> {
+        match self.opt.selected.cloned() {
+            PrereqOpt::Any => None,
+            PrereqOpt::Strict => Some(PrereqPolicy::Strict),
+            PrereqOpt::Lenient => Some(PrereqPolicy::Lenient),
+            PrereqOpt::Threshold => self.threshold.get_value().map(PrereqPolicy::Threshold),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct FloatEntry {
     input: Signal<String>,
@@ -166,6 +259,15 @@ impl FilterEditor {
         let finished = BoolEntry::from_bool("finished", filter.finished);
         let suspended = BoolEntry::from_bool("suspended", filter.suspended);
         let pending = BoolEntry::from_bool("pending", filter.pending);
+        let trivial = BoolEntry::from_bool("trivial", filter.trivial);
+
+        let ahead = NumEntry::new(
+            "days ahead",
+            filter.ahead.map(|ahead| ahead.as_secs_f32() / 86400.),
+        );
+        let new_cards_per_day = NumEntry::new("new per day", filter.new_cards_per_day);
+        let prereq = PrereqEntry::from_policy(filter.prereq);
+        let bury_siblings = Signal::new_in_scope(filter.bury_siblings, ScopeId::APP);
 
         Self {
             filter_name,
@@ -177,6 +279,11 @@ impl FilterEditor {
             finished,
             suspended,
             pending,
+            trivial,
+            ahead,
+            new_cards_per_day,
+            prereq,
+            bury_siblings,
         }
     }
 
@@ -184,17 +291,7 @@ impl FilterEditor {
         let selv = self.clone();
         Signal::memo(move || {
             info!("cardfilter memo!");
-
-            CardFilter {
-                recall: selv.recall.get_value(),
-                rec_recall: selv.rec_recall.get_value(),
-                stability: selv.stability.get_value(),
-                rec_stability: selv.rec_stability.get_value(),
-                finished: selv.finished.get_value(),
-                suspended: selv.suspended.get_value(),
-                pending: selv.pending.get_value(),
-                lapses: selv.lapses.get_value(),
-            }
+            selv.to_filter()
         })
     }
 
@@ -208,6 +305,14 @@ impl FilterEditor {
             suspended: self.suspended.get_value(),
             pending: self.pending.get_value(),
             lapses: self.lapses.get_value(),
+            trivial: self.trivial.get_value(),
+            ahead: self
+                .ahead
+                .get_value()
+                .map(|days: f32| Duration::from_secs_f32(days.max(0.) * 86400.)),
+            prereq: self.prereq.get_value(),
+            new_cards_per_day: self.new_cards_per_day.get_value(),
+            bury_siblings: self.bury_siblings.cloned(),
         }
     }
 }
@@ -224,6 +329,11 @@ pub fn FilterComp(editor: FilterEditor) -> Element {
         finished,
         suspended,
         pending,
+        trivial,
+        ahead,
+        new_cards_per_day,
+        prereq,
+        bury_siblings,
     } = editor;
     rsx! {
         div {
@@ -237,6 +347,73 @@ pub fn FilterComp(editor: FilterEditor) -> Element {
             BoolEntryRender { name: finished.name.clone(), opt: finished.opt.clone() },
             BoolEntryRender { name: suspended.name.clone(), opt: suspended.opt.clone() },
             BoolEntryRender { name: pending.name.clone(), opt: pending.opt.clone() },
+            BoolEntryRender { name: trivial.name.clone(), opt: trivial.opt.clone() },
+            NumEntryRender { input: ahead.input, name: ahead.name.clone() },
+            NumEntryRender { input: new_cards_per_day.input, name: new_cards_per_day.name.clone() },
+            PrereqEntryRender { opt: prereq.opt.clone(), threshold: prereq.threshold.clone() },
+            CheckEntryRender { name: "bury siblings", value: bury_siblings },
+        }
+    }
+}
+
+#[component]
+fn NumEntryRender(mut input: Signal<String>, name: Arc<String>) -> Element {
+    rsx! {
+        div {
+            class: "flex items-center gap-x-2",
+            label {
+                class: "text-sm font-medium text-gray-700 w-28",
+                "{name}:"
+            }
+
+            input {
+                class: "w-20 p-1 border rounded focus:ring focus:ring-blue-200",
+                value: "{input}",
+                oninput: move |evt| {
+                    let new_value = evt.value().clone();
+                    if new_value.parse::<f64>().is_ok() || new_value.is_empty() {
+                        input.set(new_value);
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[component]
+fn CheckEntryRender(name: &'static str, mut value: Signal<bool>) -> Element {
+    rsx! {
+        div {
+            class: "flex items-center gap-x-2",
+            label {
+                class: "text-sm font-medium text-gray-700 w-28",
+                "{name}:"
+            }
+
+            input {
+                r#type: "checkbox",
+                checked: value(),
+                onchange: move |evt| value.set(evt.checked()),
+            }
+        }
+    }
+}
+
+#[component]
+fn PrereqEntryRender(opt: DropDownMenu<PrereqOpt>, threshold: NumEntry) -> Element {
+    rsx! {
+        div {
+            class: "flex items-center gap-x-2",
+            label {
+                class: "text-sm font-medium text-gray-700 w-28",
+                "prereqs:"
+            }
+
+            DropComponent {options: opt.options.clone(), selected: opt.selected}
+
+            if matches!(opt.selected.cloned(), PrereqOpt::Threshold) {
+                NumEntryRender { input: threshold.input, name: threshold.name.clone() }
+            }
         }
     }
 }
@@ -17,6 +17,7 @@ pub enum CardTy {
     Instance,
     Class,
     Unfinished,
+    Statement,
 }
 
 impl CardTy {
@@ -26,6 +27,7 @@ impl CardTy {
             CardTy::Instance => CType::Instance,
             CardTy::Class => CType::Class,
             CardTy::Unfinished => CType::Unfinished,
+            CardTy::Statement => CType::Statement,
         }
     }
 
@@ -36,7 +38,7 @@ impl CardTy {
             CType::Unfinished => Self::Unfinished,
             CType::Attribute => Self::Normal,
             CType::Class => Self::Class,
-            CType::Statement => Self::Unfinished,
+            CType::Statement => Self::Statement,
             CType::Event => Self::Normal,
         }
     }
@@ -49,6 +51,45 @@ impl Display for CardTy {
             CardTy::Instance => "instance",
             CardTy::Class => "class",
             CardTy::Unfinished => "unfinished",
+            CardTy::Statement => "statement",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+/// Whether a statement is asked as a true/false prompt when reviewing, and what the answer is.
+#[derive(EnumIter, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum TruthOpt {
+    NotAsked,
+    True,
+    False,
+}
+
+impl TruthOpt {
+    pub fn to_truth(self) -> Option<bool> {
+        match self {
+            TruthOpt::NotAsked => None,
+            TruthOpt::True => Some(true),
+            TruthOpt::False => Some(false),
+        }
+    }
+
+    pub fn from_truth(truth: Option<bool>) -> Self {
+        match truth {
+            None => Self::NotAsked,
+            Some(true) => Self::True,
+            Some(false) => Self::False,
+        }
+    }
+}
+
+impl Display for TruthOpt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            TruthOpt::NotAsked => "not asked",
+            TruthOpt::True => "true",
+            TruthOpt::False => "false",
         };
 
         write!(f, "{s}")
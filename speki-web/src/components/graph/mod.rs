@@ -198,7 +198,10 @@ pub fn GraphRepRender(
                                 return;
                             };
                             let mut first = app.load_card(from).await;
-                            first.card.write().rm_dependency(to).await;
+                            if let Err(e) = first.card.write().rm_dependency(to).await {
+                                tracing::error!("failed to remove dependency: {e}");
+                                return;
+                            }
 
                             inner.set_origin(Node::Card(from)).await;
                             if is_dom_rendered(&cyto_id) {
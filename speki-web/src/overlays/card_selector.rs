@@ -62,6 +62,7 @@ impl CardSelector {
                         suspended: editor.suspended.get_value(),
                         pending: editor.pending.get_value(),
                         lapses: editor.lapses.get_value(),
+                        ..Default::default()
                     })
                 })
             }
@@ -3,16 +3,22 @@ use std::sync::Arc;
 use dioxus::prelude::*;
 use speki_core::{
     audio::AudioId,
-    card::{BaseCard, CardId},
-    CardType, ClassCard, InstanceCard, NormalCard, UnfinishedCard,
+    card::{BaseCard, CardId, CardWarning, LengthLimits},
+    CardType, ClassCard, InstanceCard, NormalCard, StatementCard, UnfinishedCard,
 };
 use speki_web::{CardEntry, Node, NodeId, NodeMetadata};
+use strum::IntoEnumIterator;
 use tracing::info;
 
 use crate::{
     components::{
-        backside::BackPutRender, cardref::CardRefRender, frontside::FrontPutRender,
-        graph::GraphRepRender, BackPut, CardRef, CardTy, DropDownMenu, FrontPut, GraphRep,
+        backside::{BackOpts, BackPutRender},
+        cardref::CardRefRender,
+        dropdown::DropComponent,
+        frontside::FrontPutRender,
+        frontside::TruthOpt,
+        graph::GraphRepRender,
+        BackPut, CardRef, CardTy, DropDownMenu, FrontPut, GraphRep,
     },
     overlays::{
         card_selector::{CardSelector, MyClosure},
@@ -98,6 +104,7 @@ pub struct CardViewer {
     pub title: Option<String>,
     pub front: FrontPut,
     pub back: BackPut,
+    pub truth: DropDownMenu<TruthOpt>,
     pub concept: CardRef,
     pub dependencies: Signal<Vec<CardId>>,
     pub dependents: Signal<Vec<Node>>,
@@ -117,6 +124,7 @@ impl PartialEq for CardViewer {
         self.title == other.title
             && self.front == other.front
             && self.back == other.back
+            && self.truth == other.truth
             && self.concept == other.concept
             && self.dependencies == other.dependencies
             && self.dependents == other.dependents
@@ -202,6 +210,11 @@ impl CardViewer {
 
         bck.text.clone().set(back);
 
+        let truth = DropDownMenu::new(
+            TruthOpt::iter(),
+            Some(TruthOpt::from_truth(card.card.read().truth())),
+        );
+
         let graph = graph.with_label(frnt.text.clone());
 
         let dependencies: Signal<Vec<CardId>> =
@@ -250,6 +263,7 @@ impl CardViewer {
         Self {
             front: frnt,
             back: bck,
+            truth,
             dependents,
             dependencies,
             graph,
@@ -324,6 +338,7 @@ impl CardViewer {
             concept,
             front,
             back,
+            truth: DropDownMenu::new(TruthOpt::iter(), None),
             graph,
             is_done: Signal::new_in_scope(false, ScopeId(3)),
             old_card: Signal::new_in_scope(None, ScopeId(3)),
@@ -345,6 +360,7 @@ impl CardViewer {
     async fn reset(&self) {
         self.front.reset();
         self.back.reset();
+        self.truth.reset();
         self.concept.reset();
         self.dependencies.clone().write().clear();
         self.old_card.clone().set(None);
@@ -392,6 +408,10 @@ impl CardViewer {
                 })
             }
             CardTy::Unfinished => CardType::Unfinished(UnfinishedCard { front }),
+            CardTy::Statement => CardType::Statement(StatementCard {
+                front,
+                truth: self.truth.selected.cloned().to_truth(),
+            }),
         };
 
         Some(CardRep {
@@ -479,7 +499,9 @@ impl CardViewer {
                         let old_card = selv.old_card.cloned();
                         async move {
                             if let Some(mut old_card) = old_card {
-                                old_card.card.write().add_dependency(card.id()).await;
+                                if let Err(e) = old_card.card.write().add_dependency(card.id()).await {
+                                    tracing::error!("{e}");
+                                }
                             }
                         }
                     }
@@ -544,7 +566,12 @@ impl CardViewer {
                                 APP.read().inner().provider.audios.save_item(audio).await;
                             }
 
-                            let card = APP.read().inner().card_provider().save_basecard(basecard).await;
+                            let provider = APP.read().inner().card_provider();
+                            for warning in basecard.length_warnings(&provider, Default::default()).await {
+                                tracing::warn!("{warning}");
+                            }
+
+                            let card = provider.save_basecard(basecard).await;
                             let inner_card = Arc::unwrap_or_clone(card);
                             let card = CardEntry::new(inner_card.clone());
                             if let Some(hook) = selveste.save_hook.clone() {
@@ -573,12 +600,31 @@ impl CardViewer {
         let selv = self.clone();
         let is_short = IS_SHORT.cloned();
         let overlay = self.overlay.clone();
+        let limits = LengthLimits::default();
+        let front_warning = limits.check_front(&self.front.text.read());
+        let back_warning = if *self.back.dropdown.selected.read() == BackOpts::Text {
+            limits.check_back(&self.back.text.read())
+        } else {
+            None
+        };
+
         rsx! {
             FrontPutRender { dropdown: self.front.dropdown.clone(), text: self.front.text.clone(), audio: self.front.audio.clone() }
+            { length_warning(front_warning) }
 
             match ty {
                 CardTy::Unfinished => rsx! {},
 
+                CardTy::Statement => rsx! {
+                    div {
+                        class: "block text-gray-700 text-sm font-medium mb-2",
+                        if !is_short {
+                            "Asked as true or false:"
+                        }
+                        DropComponent { options: selv.truth.options.clone(), selected: selv.truth.selected }
+                    }
+                },
+
                 CardTy::Normal => rsx! {
                     BackPutRender {
                         text: selv.back.text.clone(),
@@ -587,6 +633,7 @@ impl CardViewer {
                         overlay: overlay.clone(),
                         audio: selv.back.audio.clone(),
                     }
+                    { length_warning(back_warning.clone()) }
                 },
                 CardTy::Class => rsx! {
                     BackPutRender {
@@ -596,6 +643,7 @@ impl CardViewer {
                         overlay: overlay.clone(),
                         audio: selv.back.audio.clone(),
                     }
+                    { length_warning(back_warning.clone()) }
 
 
                     if !is_short {
@@ -643,6 +691,7 @@ impl CardViewer {
                         overlay: overlay.clone(),
                         audio: selv.back.audio.clone(),
                     }
+                    { length_warning(back_warning.clone()) }
 
                     if !is_short {
                         div {
@@ -740,3 +789,16 @@ pub fn CardViewerRender(props: CardViewer) -> Element {
         }
     }
 }
+
+fn length_warning(warning: Option<CardWarning>) -> Element {
+    let Some(warning) = warning else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div {
+            class: "text-sm text-yellow-700 mb-2",
+            "⚠ {warning}"
+        }
+    }
+}
@@ -74,6 +74,12 @@ async fn name(dy: DynCard) -> String {
             let col = APP.read().load_collection(id).await;
             format!("collection: {col}")
         }
+        DynCard::Limit(inner, max) => {
+            format!("first {max} of {}", Box::pin(name(*inner)).await)
+        }
+        DynCard::Sample(inner, max, _) => {
+            format!("{max} random from {}", Box::pin(name(*inner)).await)
+        }
     }
 }
 
@@ -15,7 +15,7 @@ use card_selector::CardSelectorRender;
 use cardviewer::CardViewerRender;
 use colviewer::ColViewRender;
 use dioxus::prelude::*;
-use reviewsession::ReviewRender;
+use reviewsession::{ReviewRender, SessionSummaryRender};
 use std::fmt::Debug;
 use textinput::TextInputRender;
 use yesno::YesnoRender;
@@ -90,6 +90,14 @@ pub fn Overender(overlay: Signal<Option<OverlayEnum>>, root: Element) -> Element
                     div {
                         button {
                             onclick: move |_| {
+                                // Quitting a review early shows its summary first, the summary's close button ends it.
+                                if let Some(OverlayEnum::Review(review)) = overlay.cloned() {
+                                    if !review.finished.cloned() {
+                                        review.quit.clone().set(true);
+                                        return;
+                                    }
+                                }
+
                                 // Note that pressing X will close its parents overlay, which represents the current view.
                                 overlay.clone().set(None);
                             },
@@ -97,6 +105,12 @@ pub fn Overender(overlay: Signal<Option<OverlayEnum>>, root: Element) -> Element
                         }
 
                         match elm {
+                            OverlayEnum::Review(elm) if elm.finished.cloned() => rsx!{
+                                SessionSummaryRender {
+                                    summary: elm.summary(),
+                                    closed: elm.closed,
+                                }
+                            },
                             OverlayEnum::Review(elm) => rsx!{
                                 ReviewRender {
                                     front: elm.front,
@@ -107,6 +121,10 @@ pub fn Overender(overlay: Signal<Option<OverlayEnum>>, root: Element) -> Element
                                     tot: elm.tot_len,
                                     overlay: elm.overlay.clone(),
                                     dependencies:elm.dependencies.clone(),
+                                    summary: elm.summary,
+                                    started: elm.started,
+                                    scheme: elm.scheme,
+                                    bindings: elm.bindings.clone(),
                                 }
                             },
                             OverlayEnum::Colviewer(elm) => rsx!{
@@ -136,6 +154,7 @@ pub fn Overender(overlay: Signal<Option<OverlayEnum>>, root: Element) -> Element
                                     title: elm.title.clone(),
                                     front: elm.front.clone(),
                                     back: elm.back.clone(),
+                                    truth: elm.truth.clone(),
                                     concept: elm.concept.clone(),
                                     dependencies: elm.dependencies.clone(),
                                     dependents: elm.dependents.clone(),
@@ -1,8 +1,13 @@
 use dioxus::prelude::*;
 use speki_web::CardEntry;
-use std::{rc::Rc, sync::Arc};
+use std::{rc::Rc, sync::Arc, time::Duration};
 
-use speki_core::{card::CardId, cardfilter::CardFilter, recall_rate::Recall};
+use speki_core::{
+    card::CardId,
+    cardfilter::CardFilter,
+    recall_rate::{Recall, SessionSummary},
+    session::{GradingScheme, KeyBindings, ReviewAction, ReviewSettings},
+};
 use tracing::info;
 
 use crate::{
@@ -16,12 +21,38 @@ use crate::{
 
 use super::OverlayEnum;
 
+/// Takes the card currently being reviewed off the front of the queue.
+fn next_card(mut queue: Signal<Vec<CardId>>) {
+    let mut queue = queue.write();
+    if !queue.is_empty() {
+        queue.remove(0);
+    }
+}
+
+/// Adds the review to the session summary, counting the time since the previous review as time spent.
+fn record_review(
+    mut summary: Signal<SessionSummary>,
+    started: Duration,
+    recall: Recall,
+    left: usize,
+) {
+    let now = APP.read().inner().time_provider.current_time();
+    let spent = now.saturating_sub(started + summary.read().time_spent);
+    summary.write().record(recall, spent);
+
+    if left == 0 {
+        info!("session finished: {}", summary.read());
+    }
+}
+
 #[component]
 fn RecallButton(
     recall: Recall,
     card: CardEntry,
-    mut queue: Signal<Vec<CardId>>,
+    queue: Signal<Vec<CardId>>,
     mut show_backside: Signal<bool>,
+    summary: Signal<SessionSummary>,
+    started: Duration,
 ) -> Element {
     let label = match recall {
         Recall::None => "😡",
@@ -40,7 +71,8 @@ fn RecallButton(
                     card.card.write()
                         .add_review(recall)
                         .await;
-                    queue.write().pop();
+                    next_card(queue);
+                    record_review(summary, started, recall, queue.read().len());
                     show_backside.set(false);
                 });
             },
@@ -50,17 +82,57 @@ fn RecallButton(
     }
 }
 
+/// Reveals a statement's truth value after the user has guessed it.
+#[component]
+fn TruthButtons(
+    mut show_backside: Signal<bool>,
+    card: CardEntry,
+    mut guess: Signal<Option<(CardId, bool)>>,
+) -> Element {
+    rsx! {
+        div {
+            class: "flex gap-4 justify-center items-center",
+
+            for answer in [true, false] {
+                button {
+                    class: "inline-flex items-center text-white bg-gray-800 border-0 py-1 px-3 focus:outline-none hover:bg-gray-700 rounded text-base",
+                    onclick: {
+                        let card = card.clone();
+                        move |_| {
+                            guess.set(Some((card.id(), answer)));
+                            show_backside.set(true);
+
+                            if let Some(audio) = card.card.read().back_audio.clone() {
+                                play_audio(audio.data, "audio/mpeg");
+                            }
+                        }
+                    },
+                    "{answer}"
+                }
+            }
+        }
+    }
+}
+
 #[component]
 fn ReviewButtons(
     mut show_backside: Signal<bool>,
     card: CardEntry,
     queue: Signal<Vec<CardId>>,
+    summary: Signal<SessionSummary>,
+    started: Duration,
+    scheme: GradingScheme,
+    guess: Signal<Option<(CardId, bool)>>,
 ) -> Element {
+    let is_truth_prompt = card.card.read().truth().is_some();
+
     rsx! {
         div {
             class: "flex flex-col items-center justify-center h-[68px]",
 
-            if !show_backside() {
+            if !show_backside() && is_truth_prompt {
+                TruthButtons { show_backside, card: card.clone(), guess }
+            } else if !show_backside() {
                 button {
                     class: "inline-flex items-center text-white bg-gray-800 border-0 py-1 px-3 focus:outline-none hover:bg-gray-700 rounded text-base",
                     onclick: move |_| {
@@ -79,12 +151,14 @@ fn ReviewButtons(
                 div {
                     class: "flex gap-4 justify-center items-center",
 
-                    for recall in [Recall::None, Recall::Late, Recall::Some, Recall::Perfect] {
+                    for recall in scheme.grades().iter().copied() {
                          RecallButton {
                             recall,
                             card: card.clone(),
                             queue: queue.clone(),
-                            show_backside: show_backside.clone()
+                            show_backside: show_backside.clone(),
+                            summary,
+                            started,
                         }
                     }
                 }
@@ -103,18 +177,18 @@ pub fn ReviewRender(
     tot: usize,
     overlay: Signal<Option<OverlayEnum>>,
     dependencies: Resource<Vec<(CardEntry, Signal<Option<OverlayEnum>>)>>,
+    summary: Signal<SessionSummary>,
+    started: Duration,
+    scheme: GradingScheme,
+    bindings: KeyBindings,
 ) -> Element {
     let card2 = card.clone();
     let log_event = move |event: Rc<KeyboardData>| {
         let mut card = card2.clone();
         info!("reviewing..");
         let bck = show_backside.cloned();
-        let recall = match event.key().to_string().as_str() {
-            "1" if bck => Recall::None,
-            "2" if bck => Recall::Late,
-            "3" if bck => Recall::Some,
-            "4" if bck => Recall::Perfect,
-            " " => {
+        let recall = match bindings.action(&event.key().to_string(), scheme) {
+            Some(ReviewAction::Reveal) => {
                 show_backside.clone().set(true);
 
                 if let Some(audio) = card.card.read().back_audio.clone() {
@@ -123,9 +197,19 @@ pub fn ReviewRender(
 
                 return;
             }
+            Some(ReviewAction::Suspend) => {
+                next_card(queue);
+                show_backside.clone().set(false);
+                spawn(async move {
+                    card.card.write().set_suspend(true).await;
+                });
+                return;
+            }
+            Some(ReviewAction::Grade(recall)) if bck => recall,
             _ => return,
         };
-        queue.clone().write().pop();
+        next_card(queue);
+        record_review(summary, started, recall, queue.read().len());
         show_backside.clone().set(false);
         spawn(async move {
             card.card.write().add_review(recall).await;
@@ -170,7 +254,7 @@ pub fn ReviewRender(
                             class: "flex-none w-full md:w-1/2 p-4 box-border overflow-y-auto overflow-x-hidden order-2 md:order-1",
                             style: "min-height: 0; max-height: 100%;",
                              CardSides {
-                                front, back, queue, card, show_backside
+                                front, back, queue, card, show_backside, summary, started, scheme
                              }
                         }
                     }
@@ -188,23 +272,37 @@ pub struct ReviewState {
     pub back: Resource<String>,
     pub show_backside: Signal<bool>,
     pub is_done: Memo<bool>,
+    /// All cards have been reviewed or the user quit, the summary is shown until it's closed.
+    pub finished: Memo<bool>,
+    pub quit: Signal<bool>,
+    pub closed: Signal<bool>,
     pub overlay: Signal<Option<OverlayEnum>>,
+    pub summary: Signal<SessionSummary>,
+    pub started: Duration,
+    pub scheme: GradingScheme,
+    pub bindings: KeyBindings,
 }
 
 impl ReviewState {
     pub async fn new_with_filter(cards: Vec<CardEntry>, filter: CardFilter) -> Self {
-        let mut filtered = vec![];
-
-        for card in cards {
-            if filter.filter(Arc::new(card.card.cloned())).await {
-                filtered.push(card);
-            }
-        }
-
-        Self::new(filtered)
+        let cards = cards
+            .into_iter()
+            .map(|card| Arc::new(card.card.cloned()))
+            .collect();
+
+        let filtered = APP
+            .read()
+            .inner()
+            .filter_cards(cards, &filter)
+            .await
+            .into_iter()
+            .map(|card| CardEntry::new(Arc::unwrap_or_clone(card)))
+            .collect();
+
+        Self::new(filtered).await
     }
 
-    pub fn new(cards: Vec<CardEntry>) -> Self {
+    pub async fn new(cards: Vec<CardEntry>) -> Self {
         info!("start review for {} cards", cards.len());
 
         let mut thecards = vec![];
@@ -216,12 +314,15 @@ impl ReviewState {
         let overlay: Signal<Option<OverlayEnum>> = Signal::new_in_scope(None, ScopeId::APP);
         let queue: Signal<Vec<CardId>> = Signal::new_in_scope(thecards, ScopeId::APP);
 
-        let is_done: Memo<bool> =
-            ScopeId::APP.in_runtime(|| use_memo(move || queue.read().last().is_none()));
+        let quit: Signal<bool> = Signal::new_in_scope(false, ScopeId::APP);
+        let closed: Signal<bool> = Signal::new_in_scope(false, ScopeId::APP);
+        let finished: Memo<bool> =
+            ScopeId::APP.in_runtime(|| use_memo(move || quit() || queue.read().is_empty()));
+        let is_done: Memo<bool> = ScopeId::APP.in_runtime(|| use_memo(move || closed.cloned()));
 
         let card = ScopeId::APP.in_runtime(|| {
             use_resource(move || async move {
-                match queue.read().last() {
+                match queue.read().first() {
                     Some(id) => {
                         let card = APP.read().load_card(*id).await;
                         if let Some(audio) = card.card.read().front_audio.clone() {
@@ -255,7 +356,7 @@ impl ReviewState {
             use_resource(move || async move {
                 info!("updating front card resource in review!");
                 match card.cloned() {
-                    Some(Some(card)) => card.to_string(),
+                    Some(Some(card)) => card.card.read().display_review_front().await,
                     _ => "".to_string(),
                 }
             })
@@ -275,7 +376,15 @@ impl ReviewState {
             })
         });
 
+        let settings = match APP.read().inner().load_review_settings().await {
+            Ok(settings) => settings,
+            Err(e) => {
+                tracing::error!("invalid review settings, using the defaults: {e}");
+                ReviewSettings::default()
+            }
+        };
         let tot_len = queue.read().len();
+        let started = APP.read().inner().time_provider.current_time();
         Self {
             card,
             tot_len,
@@ -284,8 +393,66 @@ impl ReviewState {
             show_backside: Signal::new_in_scope(Default::default(), ScopeId::APP),
             dependencies,
             is_done,
+            finished,
+            quit,
+            closed,
             queue,
             overlay,
+            summary: Signal::new_in_scope(Default::default(), ScopeId::APP),
+            started,
+            scheme: settings.scheme,
+            bindings: settings.bindings,
+        }
+    }
+}
+
+impl ReviewState {
+    /// The grades given so far, also when the session was quit before the queue ran out.
+    pub fn summary(&self) -> SessionSummary {
+        self.summary.cloned()
+    }
+}
+
+#[component]
+pub fn SessionSummaryRender(summary: SessionSummary, mut closed: Signal<bool>) -> Element {
+    let SessionSummary {
+        reviewed,
+        again,
+        hard,
+        good,
+        easy,
+        time_spent,
+    } = summary;
+    let minutes = time_spent.as_secs() / 60;
+    let seconds = time_spent.as_secs() % 60;
+
+    rsx! {
+        div {
+            class: "flex flex-col items-center gap-4 p-8",
+
+            h2 {
+                class: "text-2xl text-gray-700",
+                "Session finished"
+            }
+
+            p {
+                class: "text-lg text-gray-800",
+                "reviewed {reviewed} cards in {minutes}m {seconds}s"
+            }
+
+            div {
+                class: "flex gap-6 text-lg",
+                span { "😡 {again}" }
+                span { "😠 {hard}" }
+                span { "🙂 {good}" }
+                span { "😁 {easy}" }
+            }
+
+            button {
+                class: "inline-flex items-center text-white bg-gray-800 border-0 py-1 px-3 focus:outline-none hover:bg-gray-700 rounded text-base",
+                onclick: move |_| closed.set(true),
+                "close"
+            }
         }
     }
 }
@@ -332,7 +499,7 @@ fn Infobar(
 }
 
 #[component]
-fn Suspend(card: CardEntry, mut queue: Signal<Vec<CardId>>) -> Element {
+fn Suspend(card: CardEntry, queue: Signal<Vec<CardId>>) -> Element {
     let is_suspended = card.card.read().is_suspended();
     let txt = if is_suspended { "unsuspend" } else { "suspend" };
 
@@ -344,7 +511,7 @@ fn Suspend(card: CardEntry, mut queue: Signal<Vec<CardId>>) -> Element {
                 spawn(async move {
                     let mut card = card;
                     card.card.write().set_suspend(!is_suspended).await;
-                    queue.write().pop();
+                    next_card(queue);
                 });
             },
             "{txt}"
@@ -388,7 +555,9 @@ fn RenderDependencies(
                             let fun = MyClosure::new(move |card: CardEntry| {
                                 let mut old_card = currcard.clone();
                                 async move {
-                                    old_card.card.write().add_dependency(card.id()).await;
+                                    if let Err(e) = old_card.card.write().add_dependency(card.id()).await {
+                                        tracing::error!("{e}");
+                                    }
                                     let _ = queue.write();
                                 }
                             });
@@ -426,6 +595,9 @@ fn CardSides(
     show_backside: Signal<bool>,
     card: CardEntry,
     queue: Signal<Vec<CardId>>,
+    summary: Signal<SessionSummary>,
+    started: Duration,
+    scheme: GradingScheme,
 ) -> Element {
     let backside_visibility_class = if show_backside() {
         "opacity-100 visible"
@@ -433,6 +605,15 @@ fn CardSides(
         "opacity-0 invisible"
     };
 
+    let guess: Signal<Option<(CardId, bool)>> = use_signal(|| None);
+    let back = match (card.card.read().truth(), guess()) {
+        (Some(truth), Some((id, guessed))) if id == card.id() => {
+            let mark = if truth == guessed { "✅" } else { "❌" };
+            format!("{back} {mark}")
+        }
+        _ => back,
+    };
+
     rsx! {
         div {
             class: "flex flex-col items-center w-full",
@@ -462,6 +643,10 @@ fn CardSides(
                     show_backside,
                     card,
                     queue,
+                    summary,
+                    started,
+                    scheme,
+                    guess,
                 }
             }
         }
@@ -34,6 +34,7 @@ pub fn Add() -> Element {
             title: selv.viewer.title.clone(),
             front: selv.viewer.front.clone(),
             back: selv.viewer.back.clone(),
+            truth: selv.viewer.truth.clone(),
             concept: selv.viewer.concept.clone(),
             dependencies: selv.viewer.dependencies.clone(),
             dependents: selv.viewer.dependents.clone(),
@@ -1,7 +1,7 @@
 use std::{fmt::Debug, sync::Arc};
 
 use dioxus::prelude::*;
-use speki_core::{cardfilter::CardFilter, collection::Collection};
+use speki_core::{cardfilter::CardFilter, collection::Collection, session::GradingScheme};
 use tracing::info;
 
 use crate::{
@@ -186,6 +186,50 @@ impl RecallDist {
     }
 }
 
+/// Switches between pass/fail and four-grade reviews, saved in the review settings.
+#[component]
+fn GradingToggle() -> Element {
+    let mut settings =
+        use_resource(|| async move { APP.read().inner().load_review_settings().await.ok() });
+    let two_button = settings
+        .cloned()
+        .flatten()
+        .is_some_and(|settings| settings.scheme == GradingScheme::TwoButton);
+
+    rsx! {
+        label {
+            class: "flex items-center gap-x-2 text-sm text-gray-700 mb-8",
+            input {
+                r#type: "checkbox",
+                checked: two_button,
+                onchange: move |evt| {
+                    let scheme = if evt.checked() {
+                        GradingScheme::TwoButton
+                    } else {
+                        GradingScheme::FourButton
+                    };
+
+                    spawn(async move {
+                        let result = match APP.read().inner().load_review_settings().await {
+                            Ok(mut new_settings) => {
+                                new_settings.scheme = scheme;
+                                APP.read().inner().save_review_settings(new_settings).await
+                            }
+                            Err(e) => Err(e),
+                        };
+
+                        if let Err(e) = result {
+                            tracing::error!("couldn't save review settings: {e}");
+                        }
+                        settings.restart();
+                    });
+                },
+            }
+            "pass/fail grading"
+        }
+    }
+}
+
 #[component]
 fn RenderCols(
     filter: CardFilter,
@@ -208,13 +252,15 @@ fn RenderCols(
                     let filter = filter.clone();
                     spawn(async move {
                         let cards = APP.read().load_all(Some(filter)).await;
-                        let revses = OverlayEnum::Review(ReviewState::new(cards));
+                        let revses = OverlayEnum::Review(ReviewState::new(cards).await);
                         overlay.clone().set(Some(revses));
                     });
                 },
                 "review all"
             }
 
+            GradingToggle {}
+
             for (col, dist, filter) in colfil {
                 div {
                     class: "flex flex-col mb-8",
@@ -30,13 +30,7 @@ impl App {
         Self(Arc::new(speki_core::App::new(
             speki_core::SimpleRecall,
             WasmTime,
-            DexieProvider::new(),
-            DexieProvider::new(),
-            DexieProvider::new(),
-            DexieProvider::new(),
-            DexieProvider::new(),
-            DexieProvider::new(),
-            DexieProvider::new(),
+            speki_core::Provider::new(DexieProvider::new()),
         )))
     }
 